@@ -31,6 +31,7 @@ func RustVerifySignature(payload, sig, pubKeyPEM []byte) bool {
 		(*C.uint8_t)(cPayload), C.uintptr_t(len(payload)),
 		(*C.uint8_t)(cSig), C.uintptr_t(len(sig)),
 		(*C.uint8_t)(cPubKey), C.uintptr_t(len(pubKeyPEM)),
+		C.HASH_ALG_SHA256,
 	)
 
 	return bool(res)
@@ -56,6 +57,7 @@ func RustSignPayload(payload, privKeyPEM []byte) []byte {
 		(*C.uint8_t)(cPayload), C.uintptr_t(len(payload)),
 		(*C.uint8_t)(cPrivKey), C.uintptr_t(len(privKeyPEM)),
 		&cOutSig, &cOutLen, &cOutCap,
+		C.HASH_ALG_SHA256,
 	)
 
 	if !bool(success) || cOutSig == nil {
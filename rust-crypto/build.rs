@@ -1,9 +1,100 @@
 fn main() {
     let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
     cbindgen::Builder::new()
-      .with_crate(crate_dir)
-      .with_language(cbindgen::Language::C)
-      .generate()
-      .expect("Unable to generate bindings")
-      .write_to_file("cryptolib.h");
+        .with_crate(crate_dir)
+        .with_language(cbindgen::Language::C)
+        .with_define("feature", "deterministic-rng", "CRYPTO_DETERMINISTIC_RNG")
+        .with_define("feature", "bench-api", "CRYPTO_BENCH_API")
+        .generate()
+        .expect("Unable to generate bindings")
+        .write_to_file("cryptolib.h");
+
+    // Baked into `crypto_lib_version`; builds outside a git checkout report "unknown".
+    let git_hash =
+        git(&["rev-parse", "--short=12", "HEAD"]).unwrap_or_else(|| "unknown".to_string());
+    println!("cargo:rustc-env=CRYPTO_LIB_GIT_HASH={git_hash}");
+
+    // Once any rerun-if-changed path is printed Cargo stops rerunning this
+    // script on every package change, so list what cbindgen and the lockfile
+    // lookup read as well as the git files that move when HEAD does.
+    for path in ["build.rs", "src", "Cargo.lock"] {
+        println!("cargo:rerun-if-changed={path}");
+    }
+    for path in git_head_paths() {
+        println!("cargo:rerun-if-changed={path}");
+    }
+
+    // Baked into `crypto_dependency_versions`, read from the resolved lockfile
+    // so `[patch]` overrides show up as the version actually compiled in.
+    let lock = std::fs::read_to_string(
+        std::path::Path::new(&std::env::var("CARGO_MANIFEST_DIR").unwrap()).join("Cargo.lock"),
+    )
+    .unwrap_or_default();
+    let versions = ["rsa", "sha2"]
+        .iter()
+        .map(|name| format!("\"{name}\":\"{}\"", locked_version(&lock, name)))
+        .collect::<Vec<_>>()
+        .join(",");
+    println!("cargo:rustc-env=CRYPTO_DEPENDENCY_VERSIONS={{{versions}}}");
+}
+
+fn git(args: &[&str]) -> Option<String> {
+    std::process::Command::new("git")
+        .args(args)
+        .output()
+        .ok()
+        .filter(|o| o.status.success())
+        .and_then(|o| String::from_utf8(o.stdout).ok())
+        .map(|s| s.trim().to_string())
+}
+
+// `.git/HEAD` plus, when a branch is checked out, its loose ref and
+// `packed-refs`, so both committing and switching branches are seen. Paths
+// come from `git rev-parse --git-path` so worktrees resolve correctly; missing
+// files are skipped because Cargo would otherwise rerun on every build.
+fn git_head_paths() -> Vec<String> {
+    let mut names = vec!["HEAD".to_string()];
+    if let Some(head_ref) = git(&["symbolic-ref", "-q", "HEAD"]) {
+        names.push(head_ref);
+        names.push("packed-refs".to_string());
+    }
+    names
+        .iter()
+        .filter_map(|name| git(&["rev-parse", "--git-path", name]))
+        .filter(|path| std::path::Path::new(path).exists())
+        .collect()
+}
+
+// Cargo.lock lists each `[[package]]` as `name = "..."` followed by
+// `version = "..."`. When several versions of a crate are locked, the root
+// package's entry names the one this crate uses as `"name version"`.
+fn locked_version(lock: &str, name: &str) -> String {
+    let mut versions = Vec::new();
+    let mut lines = lock.lines();
+    while let Some(line) = lines.next() {
+        if line == format!("name = \"{name}\"") {
+            if let Some(v) = lines.next().and_then(|l| l.strip_prefix("version = \"")) {
+                versions.push(v.trim_end_matches('"').to_string());
+            }
+        }
+    }
+    if versions.len() > 1 {
+        let prefix = format!("\"{name} ");
+        let root = lock.split("[[package]]").find(|p| {
+            p.contains(&format!(
+                "name = \"{}\"",
+                std::env::var("CARGO_PKG_NAME").unwrap()
+            ))
+        });
+        if let Some(v) = root.and_then(|p| {
+            p.lines()
+                .find_map(|l| l.trim().strip_prefix(prefix.as_str()))
+        }) {
+            return v.split(['"', ' ']).next().unwrap_or_default().to_string();
+        }
+    }
+    versions
+        .into_iter()
+        .next()
+        .unwrap_or_else(|| "unknown".to_string())
 }
@@ -3,14 +3,2397 @@
 #include <stdint.h>
 #include <stdlib.h>
 
+/**
+ * Status codes returned by the `_ex` functions.
+ */
+#define CRYPTO_OK 0
+
+#define CRYPTO_ERR_NULL_POINTER -1
+
+#define CRYPTO_ERR_INVALID_UTF8 -2
+
+#define CRYPTO_ERR_KEY_PARSE -3
+
+#define CRYPTO_ERR_CRYPTO -4
+
+#define CRYPTO_ERR_SIGNATURE_MISMATCH -5
+
+#define CRYPTO_ERR_UNSUPPORTED_ALG -6
+
+#define CRYPTO_ERR_BUFFER_TOO_SMALL -7
+
+#define CRYPTO_ERR_PANIC -8
+
+#define CRYPTO_ERR_BAD_PASSPHRASE -9
+
+#define CRYPTO_ERR_TAG_MISMATCH -10
+
+#define CRYPTO_ERR_INVALID_LENGTH -11
+
+#define CRYPTO_ERR_MALFORMED_TOKEN -12
+
+#define CRYPTO_ERR_INVALID_JSON -13
+
+#define CRYPTO_ERR_PASSWORD_MISMATCH -14
+
+#define CRYPTO_ERR_CERT_EXPIRED -15
+
+#define CRYPTO_ERR_CERT_NOT_CA -16
+
+#define CRYPTO_ERR_UNTRUSTED_ROOT -17
+
+#define CRYPTO_ERR_IO -18
+
+#define CRYPTO_ERR_INVALID_ARGUMENT -19
+
+#define CRYPTO_ERR_TIMESTAMP_SKEW -20
+
+#define CRYPTO_ERR_THRESHOLD_NOT_MET -21
+
+#define CRYPTO_ERR_PAYLOAD_TOO_LARGE -22
+
+#define CRYPTO_ERR_CERT_REVOKED -23
+
+#define CRYPTO_ERR_INVALID_JWK -24
+
+#define CRYPTO_ERR_NOT_SUPPORTED -25
+
+#define CRYPTO_ERR_TOKEN -26
+
+#define CRYPTO_ERR_POLICY_VIOLATION -27
+
+#define CRYPTO_ERR_READ_CALLBACK -28
+
+#define CRYPTO_ERR_DIGEST_MISMATCH -29
+
+#define CRYPTO_ERR_INCONSISTENT_KEY -30
+
+#define CRYPTO_ERR_AEAD -31
+
+#define CRYPTO_ERR_PASSWORD_HASH -32
+
+/**
+ * Severity passed to the `set_log_callback` callback. A signature, tag or
+ * password that simply does not match is a warning; everything else
+ * (malformed keys, bad arguments, internal failures) is an error.
+ */
+#define CRYPTO_LOG_WARN 2
+
+#define CRYPTO_LOG_ERROR 3
+
+/**
+ * `source` selectors for `set_rng_source`.
+ */
+#define RNG_SOURCE_THREAD 0
+
+#define RNG_SOURCE_OS 1
+
+#define RNG_SOURCE_DETERMINISTIC 2
+
+/**
+ * Failed-step codes returned by `crypto_self_test`.
+ */
+#define SELF_TEST_FAILED_KEYGEN 1
+
+#define SELF_TEST_FAILED_SIGN 2
+
+#define SELF_TEST_FAILED_VERIFY 3
+
+#define SELF_TEST_FAILED_SHA256 4
+
+/**
+ * Salt length used by most PSS peers: the SHA-256 digest length.
+ */
+#define PSS_DEFAULT_SALT_LEN 32
+
+/**
+ * `hash_alg` selectors accepted by `sign_payload` and `verify_signature`.
+ */
+#define HASH_ALG_SHA256 0
+
+#define HASH_ALG_SHA384 1
+
+#define HASH_ALG_SHA512 2
+
+#define HASH_ALG_SHA1 3
+
+#define HASH_ALG_SHA3_256 4
+
+#define HASH_ALG_SHA3_384 5
+
+#define HASH_ALG_SHA3_512 6
+
+/**
+ * `padding` selectors for `rsa_max_message_len`.
+ */
+#define RSA_PADDING_OAEP_SHA256 0
+
+#define RSA_PADDING_PKCS1V15 1
+
+/**
+ * Smallest modulus `generate_rsa_keypair` will produce.
+ */
+#define RSA_MIN_GENERATED_BITS 2048
+
+/**
+ * Return values of `detect_public_key_format`.
+ */
+#define PUBKEY_FORMAT_SPKI_PEM 0
+
+#define PUBKEY_FORMAT_PKCS1_PEM 1
+
+#define PUBKEY_FORMAT_SPKI_DER 2
+
+#define PUBKEY_FORMAT_PKCS1_DER 3
+
+/**
+ * `scheme` selectors for `expected_signature_len` and
+ * `algorithm_deprecation_status`.
+ */
+#define SIG_SCHEME_RSA_PKCS1V15 0
+
+#define SIG_SCHEME_RSA_PSS 1
+
+#define SIG_SCHEME_ECDSA 2
+
+#define SIG_SCHEME_ED25519 3
+
+/**
+ * Return values of `algorithm_deprecation_status`.
+ */
+#define DEPRECATION_OK 0
+
+#define DEPRECATION_DEPRECATED 1
+
+#define DEPRECATION_FORBIDDEN 2
+
+/**
+ * Result of `keys_match` when both keys parse but do not pair up.
+ */
+#define KEYS_MISMATCH 1
+
+/**
+ * Largest digest `digest_payload` writes; a buffer this size fits every
+ * `hash_alg`.
+ */
+#define MAX_DIGEST_LEN 64
+
+/**
+ * `alg` values of a sealed signature container (`seal_signature`).
+ */
+#define SEALED_ALG_RS256 1
+
+#define SEALED_ALG_RS384 2
+
+#define SEALED_ALG_RS512 3
+
+/**
+ * RSA-PSS / SHA-256 with a 32-byte salt.
+ */
+#define SEALED_ALG_PS256 4
+
+#define SEALED_ALG_EDDSA 5
+
+/**
+ * `sig_format` selectors for the ECDSA functions: fixed-width `r || s` (what
+ * JWT ES256 uses) or ASN.1 DER.
+ */
+#define ECDSA_SIG_RAW 0
+
+#define ECDSA_SIG_DER 1
+
+/**
+ * `curve` selectors for `ecdsa_sign` and `ecdsa_verify`.
+ */
+#define ECDSA_CURVE_P256 0
+
+#define ECDSA_CURVE_P384 1
+
+#define ECDSA_CURVE_P521 2
+
+/**
+ * Largest RSA modulus, in bits, that the key parsers and key generation
+ * accept. Larger keys are rejected with `KeyParse` before any key math.
+ */
+#define RSA_MAX_MODULUS_BITS 8192
+
+/**
+ * Smallest modulus `public_key_from_components` accepts.
+ */
+#define RSA_MIN_RAW_MODULUS_BITS 1024
+
+/**
+ * How often, in bytes hashed, `verify_file_pkcs1v15_progress` reports.
+ */
+#define FILE_PROGRESS_INTERVAL (1024 * 1024)
+
+#define KID_LEN 4
+
+/**
+ * The only container version `open_signature` understands.
+ */
+#define SEALED_VERSION 1
+
+/**
+ * Length of the SHA-256 digest that opens a `sign_pkcs1v15_with_digest` record.
+ */
+#define RECORD_DIGEST_LEN 32
+
+#define MAC_TAG_LEN 32
+
+/**
+ * Salt length of RSABSSA-SHA384-PSS-Deterministic (RFC 9474 section 5), the
+ * hash output size.
+ */
+#define RSABSSA_SALT_LEN 48
+
+#define P256_RAW_SIG_LEN 64
+
+/**
+ * RSA moduli below this many bits are `Forbidden`.
+ */
+#define RSA_FORBIDDEN_BELOW_BITS 2048
+
+/**
+ * RSA moduli below this many bits (and at least `RSA_FORBIDDEN_BELOW_BITS`)
+ * are `Deprecated`.
+ */
+#define RSA_DEPRECATED_BELOW_BITS 3072
+
+#define SECP256K1_DIGEST_LEN 32
+
+#define SECP256K1_SIG_LEN 64
+
+#define AES_256_GCM_KEY_LEN 32
+
+#define AES_256_GCM_NONCE_LEN 12
+
+#define AES_GCM_TAG_LEN 16
+
+#define AES_GCM_STREAM_NONCE_LEN 7
+
+#define AES_GCM_STREAM_SEGMENT_LEN (64 * 1024)
+
+/**
+ * Streaming AES-256-GCM state created by `aes_gcm_enc_new` or
+ * `aes_gcm_dec_new`. Release it with `aes_gcm_ctx_free`.
+ */
+typedef struct AeadCtx AeadCtx;
+
+/**
+ * Incremental SHA-256 state for signing or verifying payloads fed in chunks.
+ */
+typedef struct HashCtx HashCtx;
+
+/**
+ * Matched (pair, key) index pairs from `match_signatures_begin`, read with
+ * `match_results_next`. Release it with `match_results_free`.
+ */
+typedef struct MatchResults MatchResults;
+
+/**
+ * Parsed RSA private key returned by `load_private_key`.
+ *
+ * Handles are not thread-safe for signing: callers sharing one handle across
+ * threads must synchronize access themselves.
+ */
+typedef struct PrivateKeyHandle PrivateKeyHandle;
+
+/**
+ * Parsed RSA public key returned by `load_public_key`, so hot paths can
+ * verify without re-parsing PEM on every call.
+ */
+typedef struct PublicKeyHandle PublicKeyHandle;
+
+typedef void (*LogCallback)(int32_t level, const char *msg);
+
+/**
+ * Progress sink for `verify_file_signature_progress`, given the bytes hashed
+ * so far and the file's total size.
+ */
+typedef void (*ProgressCallback)(void *ctx, uint64_t processed, uint64_t total);
+
+/**
+ * Source of payload bytes for `verify_signature_pull`: fills up to `cap`
+ * bytes at `buf` and returns how many it wrote, 0 at end of input, or a
+ * negative value on error.
+ */
+typedef intptr_t (*ReadCallback)(void *ctx, uint8_t *buf, uintptr_t cap);
+
+/**
+ * Arguments of `verify_signature_ex` bundled into one struct for
+ * `verify_signature_req`.
+ */
+typedef struct VerifyRequest {
+  const uint8_t *payload_ptr;
+  uintptr_t payload_len;
+  const uint8_t *sig_ptr;
+  uintptr_t sig_len;
+  const uint8_t *pub_key_ptr;
+  uintptr_t pub_key_len;
+  uint32_t hash_alg;
+} VerifyRequest;
+
+/**
+ * A borrowed `(ptr, len)` byte range passed in arrays across FFI.
+ */
+typedef struct Slice {
+  const uint8_t *ptr;
+  uintptr_t len;
+} Slice;
+
+/**
+ * A Rust-owned buffer returned by value, released with `free_owned_buf`.
+ * A null `ptr` means the call failed; `last_error_message` has the reason.
+ */
+typedef struct OwnedBuf {
+  uint8_t *ptr;
+  uintptr_t len;
+  uintptr_t cap;
+} OwnedBuf;
+
+/**
+ * One PEM public key in a `verify_signature_multi` or
+ * `verify_payload_with_kid` key list.
+ */
+typedef struct Slice KeyEntry;
+
+/**
+ * One segment of a scatter-gather payload for `sign_payload_iov`.
+ */
+typedef struct Slice IoSlice;
+
+/**
+ * One signer's entry for `verify_attestation`: a signature and the PEM
+ * public key it should verify under.
+ */
+typedef struct SigKeyPair {
+  struct Slice sig;
+  struct Slice key;
+} SigKeyPair;
+
+/**
+ * Registers `cb` to be called with a level and message whenever a function
+ * that returns a `CRYPTO_*` status fails. Pass null to unregister; with no
+ * callback set the library logs nothing. `msg` is only valid for the duration
+ * of the call. The callback may run on any thread that calls into the
+ * library and must not unwind.
+ */
+void set_log_callback(LogCallback cb);
+
+/**
+ * Returns a description of the last failure reported by an `_ex` function on
+ * the calling OS thread, or null if there is none.
+ *
+ * The message is thread-local: it must be read on the same OS thread that
+ * made the failing call (from Go, hold `runtime.LockOSThread` across both
+ * calls). The pointer stays valid until the next crypto call on that thread
+ * and must not be freed by the caller.
+ */
+const char *last_error_message(void);
+
+/**
+ * Discards the calling thread's last error message.
+ */
+void clear_last_error(void);
+
+/**
+ * Returns the library version and the git commit it was built from, e.g.
+ * `0.1.0 (1a2b3c4d5e6f)`, as a static NUL-terminated string. Never free it.
+ */
+const char *crypto_lib_version(void);
+
+/**
+ * Returns the versions of the `rsa` and `sha2` crates compiled into this
+ * binary as a static NUL-terminated JSON object, e.g.
+ * `{"rsa":"0.9.6","sha2":"0.10.8"}`. Never free it.
+ */
+const char *crypto_dependency_versions(void);
+
+/**
+ * Returns the algorithms this build supports through the C API as a static
+ * NUL-terminated, comma-separated list of identifiers, e.g.
+ * `RS256,RS384,...,A256GCM`. ES256K is verify-only. Builds with the `pgp`
+ * feature add `OpenPGP` (detached RSA signatures, verify-only), and builds
+ * with `pkcs11` add `PKCS11` (RSA signing with token-held keys). Never free
+ * it.
+ */
+const char *supported_algorithms(void);
+
+/**
+ * Chooses the generator behind every key generation, salt, nonce and
+ * blinding draw, process-wide, from the next draw on. Returns false and
+ * leaves the source unchanged for an unknown `source`.
+ *
+ * - `RNG_SOURCE_OS` (the default): the OS generator (`getrandom`) on every
+ *   draw, with no state in the library. Suitable for production.
+ * - `RNG_SOURCE_THREAD`: `rand`'s per-thread ChaCha generator, seeded from
+ *   the OS and reseeded periodically. Also suitable for production, with
+ *   fewer system calls, but the reseeding happens in the middle of signing.
+ * - `RNG_SOURCE_DETERMINISTIC`: the seeded test stream of `set_test_rng_seed`.
+ *   Only accepted in `deterministic-rng` builds, where it is the default.
+ *   Never for production.
+ */
+bool set_rng_source(int32_t source);
+
+#if defined(CRYPTO_DETERMINISTIC_RNG)
+/**
+ * Test-only (`deterministic-rng` feature): reseeds the RNG behind every
+ * key generation, salt, nonce and blinding draw, making them reproducible.
+ * Builds with this function must never be shipped.
+ */
+void set_test_rng_seed(uint64_t seed);
+#endif
+
+#if defined(CRYPTO_DETERMINISTIC_RNG)
+/**
+ * Test-only (`deterministic-rng` feature): writes a JSON document of
+ * reference vectors for checking other implementations byte for byte,
+ * released with `free_signature`:
+ *
+ * `{"seed":N,"vectors":[{"key":...,"payload":...,"scheme":...,"signature":...}]}`
+ *
+ * Each entry is one of a fixed set of payloads signed with one signature
+ * scheme from `supported_algorithms` (HS256 and the verify-only ES256K are
+ * left out). `key` is the PKCS#8 PEM private key, generated from a fixed seed;
+ * `payload` and `signature` are standard base64, with ECDSA signatures in the
+ * fixed-width `r || s` form. PS256 uses a 32-byte salt. The library RNG is
+ * reseeded, so random draws on other threads during the call change the
+ * output and are themselves no longer independent of it.
+ */
+bool export_test_vectors(uint8_t **out_ptr, uintptr_t *out_len, uintptr_t *out_cap);
+#endif
+
+/**
+ * Checks that signing works in this process: generates an ephemeral RSA-2048
+ * key (exercising the OS RNG), signs and verifies a fixed vector, confirms a
+ * tampered signature is rejected, and runs a SHA-256 known-answer test.
+ * Returns `CRYPTO_OK`, or the `SELF_TEST_FAILED_*` code of the first step that
+ * failed. Key generation can make this take a few hundred milliseconds.
+ */
+int32_t crypto_self_test(void);
+
+#if defined(CRYPTO_BENCH_API)
+/**
+ * Diagnostic only (`bench-api` feature): generates an ephemeral `bits`-bit
+ * RSA key, then signs a fixed small payload `iterations` times with SHA-256
+ * PKCS#1 v1.5 and returns the elapsed nanoseconds, excluding key generation.
+ * Returns 0 if `bits` is outside `RSA_MIN_GENERATED_BITS..=RSA_MAX_MODULUS_BITS`
+ * or signing fails.
+ */
+uint64_t benchmark_sign(uintptr_t bits, uint32_t iterations);
+#endif
+
+#if defined(CRYPTO_BENCH_API)
+/**
+ * Diagnostic only (`bench-api` feature): like `benchmark_sign` but times
+ * `iterations` verifications of one signature made with the ephemeral key.
+ */
+uint64_t benchmark_verify(uintptr_t bits, uint32_t iterations);
+#endif
+
 bool verify_signature(const uint8_t *payload_ptr,
                       uintptr_t payload_len,
                       const uint8_t *sig_ptr,
                       uintptr_t sig_len,
                       const uint8_t *pub_key_ptr,
-                      uintptr_t pub_key_len);
+                      uintptr_t pub_key_len,
+                      uint32_t hash_alg);
+
+/**
+ * Same as `verify_signature` but reports why verification failed as one of
+ * the `CRYPTO_*` status codes. A signature that is not exactly the modulus
+ * size returns `CRYPTO_ERR_INVALID_LENGTH`, distinct from
+ * `CRYPTO_ERR_SIGNATURE_MISMATCH`.
+ */
+int32_t verify_signature_ex(const uint8_t *payload_ptr,
+                            uintptr_t payload_len,
+                            const uint8_t *sig_ptr,
+                            uintptr_t sig_len,
+                            const uint8_t *pub_key_ptr,
+                            uintptr_t pub_key_len,
+                            uint32_t hash_alg);
+
+/**
+ * Compatibility shim for non-conforming devices that emit the RSA signature
+ * integer least-significant byte first: same as `verify_signature_ex` but
+ * reverses `sig` before verifying. Standard signatures are big-endian and
+ * must not go through this function.
+ */
+int32_t verify_signature_le(const uint8_t *payload_ptr,
+                            uintptr_t payload_len,
+                            const uint8_t *sig_ptr,
+                            uintptr_t sig_len,
+                            const uint8_t *pub_key_ptr,
+                            uintptr_t pub_key_len,
+                            uint32_t hash_alg);
+
+/**
+ * Same as `verify_signature_ex` but hashes the file at `path` (UTF-8, not
+ * NUL-terminated) by streaming it, so memory use stays flat for large
+ * artifacts. A missing or unreadable file returns `CRYPTO_ERR_IO`.
+ */
+int32_t verify_file_signature(const uint8_t *path_ptr,
+                              uintptr_t path_len,
+                              const uint8_t *sig_ptr,
+                              uintptr_t sig_len,
+                              const uint8_t *pub_key_ptr,
+                              uintptr_t pub_key_len,
+                              uint32_t hash_alg);
+
+/**
+ * Same as `verify_file_signature` but calls `progress_cb` with `ctx` as the
+ * file is hashed: after every `FILE_PROGRESS_INTERVAL` (1 MiB) and once at
+ * end of file, with `processed == total` unless the file changed size while
+ * open. The callback is informational; it cannot cancel the verification
+ * and is never called if the file cannot be opened. A null `progress_cb` is
+ * allowed and behaves like `verify_file_signature`.
+ */
+int32_t verify_file_signature_progress(const uint8_t *path_ptr,
+                                       uintptr_t path_len,
+                                       const uint8_t *sig_ptr,
+                                       uintptr_t sig_len,
+                                       const uint8_t *pub_key_ptr,
+                                       uintptr_t pub_key_len,
+                                       ProgressCallback progress_cb,
+                                       void *ctx,
+                                       uint32_t hash_alg);
+
+/**
+ * Same as `verify_signature_ex` but pulls the payload from `read_cb`, called
+ * with `ctx` until it returns 0, and hashes it chunk by chunk so it is never
+ * held in memory. A negative return (or one larger than `cap`) aborts with
+ * `CRYPTO_ERR_READ_CALLBACK`. The callback is not called again after that or
+ * after end of input, and is never called if the signature or key is null.
+ */
+int32_t verify_signature_pull(ReadCallback read_cb,
+                              void *ctx,
+                              const uint8_t *sig_ptr,
+                              uintptr_t sig_len,
+                              const uint8_t *pub_key_ptr,
+                              uintptr_t pub_key_len,
+                              uint32_t hash_alg);
+
+/**
+ * Same as `sign_payload` but binds the signature to a context label. The
+ * signed message is `context || 0x00 || payload`, hashed with `hash_alg` and
+ * signed with PKCS#1 v1.5; other implementations must frame it the same way.
+ * The context must not contain a NUL byte, which keeps the framing
+ * unambiguous, and may be empty.
+ */
+bool sign_payload_ctx(const uint8_t *context_ptr,
+                      uintptr_t context_len,
+                      const uint8_t *payload_ptr,
+                      uintptr_t payload_len,
+                      const uint8_t *priv_key_ptr,
+                      uintptr_t priv_key_len,
+                      uint8_t **out_sig_ptr,
+                      uintptr_t *out_sig_len,
+                      uintptr_t *out_sig_cap,
+                      uint32_t hash_alg);
+
+/**
+ * Verification counterpart of `sign_payload_ctx`; the context must match the
+ * one used to sign.
+ */
+bool verify_signature_ctx(const uint8_t *context_ptr,
+                          uintptr_t context_len,
+                          const uint8_t *payload_ptr,
+                          uintptr_t payload_len,
+                          const uint8_t *sig_ptr,
+                          uintptr_t sig_len,
+                          const uint8_t *pub_key_ptr,
+                          uintptr_t pub_key_len,
+                          uint32_t hash_alg);
+
+/**
+ * NON-STANDARD compatibility shim for peers that pad the bare digest without
+ * the DigestInfo prefix (`0x00 0x01 0xff.. 0x00 || H`). The signature then
+ * does not commit to `hash_alg`, so only use this for the devices that need
+ * it; everything else should call `verify_signature_ex`. Status codes match
+ * `verify_signature_ex`.
+ */
+int32_t verify_signature_raw_padding(const uint8_t *payload_ptr,
+                                     uintptr_t payload_len,
+                                     const uint8_t *sig_ptr,
+                                     uintptr_t sig_len,
+                                     const uint8_t *pub_key_ptr,
+                                     uintptr_t pub_key_len,
+                                     uint32_t hash_alg);
+
+/**
+ * Same as `verify_signature_ex` but also accepts signatures whose DigestInfo
+ * omits the AlgorithmIdentifier's NULL parameters, for older peers that
+ * encode it that way. Exactly two encodings are accepted for `hash_alg`:
+ * `SEQUENCE { SEQUENCE { OID, NULL }, OCTET STRING }` (the standard one, and
+ * the only one `verify_signature` accepts) and
+ * `SEQUENCE { SEQUENCE { OID }, OCTET STRING }`. The rest of the padding is
+ * checked as strictly as in `verify_signature_ex`. Status codes match it too.
+ */
+int32_t verify_signature_lenient(const uint8_t *payload_ptr,
+                                 uintptr_t payload_len,
+                                 const uint8_t *sig_ptr,
+                                 uintptr_t sig_len,
+                                 const uint8_t *pub_key_ptr,
+                                 uintptr_t pub_key_len,
+                                 uint32_t hash_alg);
+
+/**
+ * Same as `verify_signature_ex` with the arguments passed through a single
+ * `VerifyRequest` pointer, for call sites where per-argument FFI marshalling
+ * cost matters. A null `req` returns `CRYPTO_ERR_NULL_POINTER`.
+ */
+int32_t verify_signature_req(const struct VerifyRequest *req);
+
+/**
+ * Verifies a `payload || signature` buffer whose last `sig_len` bytes are a
+ * PKCS#1 v1.5 signature over the bytes before them. On success returns the
+ * payload length (`buf_len - sig_len`, which may be 0); on failure returns a
+ * negative `CRYPTO_*` code as `verify_signature_ex` would.
+ * `sig_len > buf_len` is `CRYPTO_ERR_INVALID_ARGUMENT`, and a payload longer
+ * than `i32::MAX` is `CRYPTO_ERR_PAYLOAD_TOO_LARGE` since its length could
+ * not be returned.
+ */
+int32_t verify_appended(const uint8_t *buf_ptr,
+                        uintptr_t buf_len,
+                        uintptr_t sig_len,
+                        const uint8_t *pub_key_ptr,
+                        uintptr_t pub_key_len,
+                        uint32_t hash_alg);
+
+/**
+ * Verifies a signature made over the base64url form of the payload instead of
+ * the payload itself, for a peer whose contract signs the encoded text. The
+ * payload is encoded with the RFC 4648 section 5 URL-safe alphabet (`-` and
+ * `_`) and no `=` padding, and those ASCII bytes are hashed with `hash_alg`
+ * and checked as PKCS#1 v1.5. Status codes match `verify_signature_ex`.
+ */
+int32_t verify_signature_b64url_payload(const uint8_t *raw_payload_ptr,
+                                        uintptr_t raw_payload_len,
+                                        const uint8_t *sig_ptr,
+                                        uintptr_t sig_len,
+                                        const uint8_t *pub_key_ptr,
+                                        uintptr_t pub_key_len,
+                                        uint32_t hash_alg);
+
+/**
+ * Like `verify_signature`, but once the pointers, `hash_alg` and the payload
+ * length limit have been checked it takes the same path whether the key fails
+ * to parse, the signature has the wrong length, or the signature does not
+ * verify, and returns a single combined result. No error detail is recorded.
+ *
+ * Secret: whether `pub_key` parses and its modulus size, and whether `sig`
+ * verifies under it. Public: the pointers, `hash_alg`, `payload_len` and
+ * `sig_len`, which may still change timing. PEM/DER decoding and the key cache
+ * are not constant time in the key bytes themselves, and a key whose modulus is
+ * not 2048 bits costs a different amount than the dummy key a parse failure runs.
+ */
+bool verify_signature_ct(const uint8_t *payload_ptr,
+                         uintptr_t payload_len,
+                         const uint8_t *sig_ptr,
+                         uintptr_t sig_len,
+                         const uint8_t *pub_key_ptr,
+                         uintptr_t pub_key_len,
+                         uint32_t hash_alg);
+
+/**
+ * Debugging aid, not for production verification paths: verifies a PKCS#1
+ * v1.5 / SHA-256 signature like `verify_signature_ex` and also writes two
+ * 32-byte digests. `out_expected_digest` gets the SHA-256 of `payload`, and
+ * `out_actual_digest` gets the digest recovered from the signature's padding
+ * (all zeros if the padding does not decode, which usually means the wrong
+ * key). Different digests mean the payloads differ; a zero recovered digest
+ * points at the key or a corrupted signature. Neither buffer is written if
+ * the key fails to parse.
+ */
+int32_t verify_signature_debug(const uint8_t *payload_ptr,
+                               uintptr_t payload_len,
+                               const uint8_t *sig_ptr,
+                               uintptr_t sig_len,
+                               const uint8_t *pub_key_ptr,
+                               uintptr_t pub_key_len,
+                               uint8_t *out_expected_digest,
+                               uint8_t *out_actual_digest);
+
+/**
+ * Same as `verify_signature_ex` but reads the hash algorithm from the first
+ * byte of the signature blob (one of the `HASH_ALG_*` values), followed by the
+ * raw PKCS#1 v1.5 signature. An unknown tag returns
+ * `CRYPTO_ERR_UNSUPPORTED_ALG`.
+ */
+int32_t verify_signature_tagged(const uint8_t *payload_ptr,
+                                uintptr_t payload_len,
+                                const uint8_t *tagged_sig_ptr,
+                                uintptr_t tagged_sig_len,
+                                const uint8_t *pub_key_ptr,
+                                uintptr_t pub_key_len);
+
+/**
+ * Same as `verify_signature` but takes the public key as DER
+ * SubjectPublicKeyInfo instead of PEM.
+ */
+bool verify_signature_der(const uint8_t *payload_ptr,
+                          uintptr_t payload_len,
+                          const uint8_t *sig_ptr,
+                          uintptr_t sig_len,
+                          const uint8_t *pub_key_ptr,
+                          uintptr_t pub_key_len,
+                          uint32_t hash_alg);
+
+/**
+ * Same as `sign_payload` but binds the signature to `timestamp` (Unix
+ * seconds) for replay protection. The signed message is the timestamp as
+ * 8 big-endian bytes (two's complement) followed by the payload, hashed with
+ * `hash_alg` and signed with PKCS#1 v1.5. The timestamp is not embedded in
+ * the signature and must be sent alongside it.
+ */
+bool sign_with_timestamp(const uint8_t *payload_ptr,
+                         uintptr_t payload_len,
+                         const uint8_t *priv_key_ptr,
+                         uintptr_t priv_key_len,
+                         int64_t timestamp,
+                         uint8_t **out_sig_ptr,
+                         uintptr_t *out_sig_len,
+                         uintptr_t *out_sig_cap,
+                         uint32_t hash_alg);
+
+/**
+ * Verifies a signature made by `sign_with_timestamp`. If `timestamp` is more
+ * than `max_skew_secs` away from `now` in either direction this returns
+ * `CRYPTO_ERR_TIMESTAMP_SKEW` without checking the signature; otherwise a bad
+ * signature returns `CRYPTO_ERR_SIGNATURE_MISMATCH` as usual.
+ */
+int32_t verify_with_timestamp(const uint8_t *payload_ptr,
+                              uintptr_t payload_len,
+                              const uint8_t *sig_ptr,
+                              uintptr_t sig_len,
+                              const uint8_t *pub_key_ptr,
+                              uintptr_t pub_key_len,
+                              int64_t timestamp,
+                              uint64_t max_skew_secs,
+                              int64_t now,
+                              uint32_t hash_alg);
+
+/**
+ * Writes the DER RFC 3161 `MessageImprint` of the payload
+ * (`SEQUENCE { AlgorithmIdentifier, OCTET STRING digest }`, NULL parameters)
+ * for building a `TimeStampReq` to a timestamp authority. The buffer is
+ * released with `free_signature`.
+ */
+bool build_tsa_imprint(const uint8_t *payload_ptr,
+                       uintptr_t payload_len,
+                       uint32_t hash_alg,
+                       uint8_t **out_ptr,
+                       uintptr_t *out_len,
+                       uintptr_t *out_cap);
+
+/**
+ * Checks a DER `MessageImprint` (for example from a TSA's `TSTInfo`) against
+ * the payload, accepting NULL or absent AlgorithmIdentifier parameters.
+ * Returns `CRYPTO_ERR_DIGEST_MISMATCH` if the digest differs and
+ * `CRYPTO_ERR_MALFORMED_TOKEN` if `imprint` is not a `MessageImprint` for
+ * `hash_alg`. The timestamp token's own signature is not checked.
+ */
+int32_t verify_tsa_imprint(const uint8_t *payload_ptr,
+                           uintptr_t payload_len,
+                           const uint8_t *imprint_ptr,
+                           uintptr_t imprint_len,
+                           uint32_t hash_alg);
+
+/**
+ * Same as `verify_signature` but takes the public key as a raw big-endian
+ * modulus and exponent instead of an encoded key. Even moduli and moduli
+ * under `RSA_MIN_RAW_MODULUS_BITS` are rejected.
+ */
+bool verify_signature_raw_rsa(const uint8_t *payload_ptr,
+                              uintptr_t payload_len,
+                              const uint8_t *sig_ptr,
+                              uintptr_t sig_len,
+                              const uint8_t *n_ptr,
+                              uintptr_t n_len,
+                              const uint8_t *e_ptr,
+                              uintptr_t e_len,
+                              uint32_t hash_alg);
+
+/**
+ * Same as `verify_signature_ex` but takes a DER X.509 certificate and verifies
+ * against the RSA key in its SubjectPublicKeyInfo. The certificate chain and
+ * validity period are not checked; see `cert_not_after`.
+ */
+int32_t verify_signature_with_cert(const uint8_t *payload_ptr,
+                                   uintptr_t payload_len,
+                                   const uint8_t *sig_ptr,
+                                   uintptr_t sig_len,
+                                   const uint8_t *cert_der_ptr,
+                                   uintptr_t cert_der_len,
+                                   uint32_t hash_alg);
+
+/**
+ * Returns the `notAfter` time of a DER X.509 certificate as a Unix timestamp,
+ * or -1 if the certificate cannot be parsed.
+ */
+int64_t cert_not_after(const uint8_t *cert_der_ptr, uintptr_t cert_der_len);
+
+/**
+ * Writes the subject Common Name of a DER X.509 certificate as UTF-8 (not
+ * NUL-terminated), released with `free_signature`. A certificate without a
+ * CN succeeds with an empty buffer. Returns false if the certificate cannot
+ * be parsed. The certificate itself is not validated.
+ */
+bool cert_subject_cn(const uint8_t *cert_der_ptr,
+                     uintptr_t cert_der_len,
+                     uint8_t **out_ptr,
+                     uintptr_t *out_len,
+                     uintptr_t *out_cap);
+
+/**
+ * Writes the subjectAltName DNS names of a DER X.509 certificate as one
+ * newline-separated list (no trailing newline), released with
+ * `free_signature`. A certificate without DNS SANs succeeds with an empty
+ * buffer. Returns false if the certificate cannot be parsed or a name
+ * contains a control character.
+ */
+bool cert_san_dns_names(const uint8_t *cert_der_ptr,
+                        uintptr_t cert_der_len,
+                        uint8_t **out_ptr,
+                        uintptr_t *out_len,
+                        uintptr_t *out_cap);
+
+/**
+ * Checks a DER certificate against a DER CRL whose signature is verified
+ * under the issuer's PEM public key. Returns `CRYPTO_OK` if the certificate
+ * is not listed, `CRYPTO_ERR_CERT_REVOKED` if it is,
+ * `CRYPTO_ERR_SIGNATURE_MISMATCH` if the CRL is not signed by that key,
+ * `CRYPTO_ERR_INVALID_ARGUMENT` if the CRL is for a different issuer and
+ * `CRYPTO_ERR_KEY_PARSE` if anything fails to parse. CRL freshness
+ * (nextUpdate) is not checked.
+ */
+int32_t cert_is_revoked(const uint8_t *cert_der_ptr,
+                        uintptr_t cert_der_len,
+                        const uint8_t *crl_der_ptr,
+                        uintptr_t crl_der_len,
+                        const uint8_t *issuer_pub_key_ptr,
+                        uintptr_t issuer_pub_key_len);
+
+/**
+ * Validates `leaf_der` up to the pinned `root_der` through
+ * `intermediates_len` DER intermediates (in any order) at the current time.
+ * See `api::verify_cert_chain` for what is and is not checked. Returns
+ * `CRYPTO_ERR_CERT_EXPIRED`, `CRYPTO_ERR_SIGNATURE_MISMATCH`,
+ * `CRYPTO_ERR_CERT_NOT_CA` or `CRYPTO_ERR_UNTRUSTED_ROOT` on an invalid chain.
+ */
+int32_t verify_cert_chain(const uint8_t *leaf_der_ptr,
+                          uintptr_t leaf_der_len,
+                          const struct Slice *intermediates,
+                          uintptr_t intermediates_len,
+                          const uint8_t *root_der_ptr,
+                          uintptr_t root_der_len);
 
 bool sign_payload(const uint8_t *payload_ptr,
+                  uintptr_t payload_len,
+                  const uint8_t *priv_key_ptr,
+                  uintptr_t priv_key_len,
+                  uint8_t **out_sig_ptr,
+                  uintptr_t *out_sig_len,
+                  uintptr_t *out_sig_cap,
+                  uint32_t hash_alg);
+
+/**
+ * Same as `sign_payload` but reports the signature length and capacity as
+ * `uint64_t` on every target, 32-bit included, so bindings get one struct
+ * layout regardless of pointer width. Release the buffer with
+ * `free_signature_u64`. Fails, releasing the signature, if either value does
+ * not fit (which cannot happen on targets up to 64 bits).
+ */
+bool sign_payload_u64(const uint8_t *payload_ptr,
+                      uintptr_t payload_len,
+                      const uint8_t *priv_key_ptr,
+                      uintptr_t priv_key_len,
+                      uint8_t **out_sig_ptr,
+                      uint64_t *out_sig_len,
+                      uint64_t *out_sig_cap,
+                      uint32_t hash_alg);
+
+/**
+ * Same as `sign_payload` but returns the signature as one `OwnedBuf`, so the
+ * length and capacity travel with the pointer. Returns a null buffer on
+ * failure.
+ */
+struct OwnedBuf sign_payload_alloc(const uint8_t *payload_ptr,
+                                   uintptr_t payload_len,
+                                   const uint8_t *priv_key_ptr,
+                                   uintptr_t priv_key_len,
+                                   uint32_t hash_alg);
+
+/**
+ * Releases a buffer returned by value as an `OwnedBuf`, with the same
+ * idempotency and return value as `free_signature`.
+ */
+bool free_owned_buf(struct OwnedBuf buf);
+
+/**
+ * Same as `sign_payload` but reports why signing failed as one of the
+ * `CRYPTO_*` status codes. The out-params are only written on `CRYPTO_OK`.
+ */
+int32_t sign_payload_ex(const uint8_t *payload_ptr,
+                        uintptr_t payload_len,
+                        const uint8_t *priv_key_ptr,
+                        uintptr_t priv_key_len,
+                        uint8_t **out_sig_ptr,
+                        uintptr_t *out_sig_len,
+                        uintptr_t *out_sig_cap,
+                        uint32_t hash_alg);
+
+/**
+ * Same as `sign_payload_ex` but takes the private key as raw big-endian
+ * components: modulus `n`, public exponent `e`, private exponent `d` and the
+ * primes `p` and `q`. The CRT values are recomputed from them. Components
+ * that do not form a valid key (`n != p*q`, `d` not inverting `e`, and so
+ * on) return `CRYPTO_ERR_INCONSISTENT_KEY`; a modulus over 8192 bits returns
+ * `CRYPTO_ERR_KEY_PARSE`.
+ */
+int32_t sign_payload_raw_rsa_priv(const uint8_t *payload_ptr,
+                                  uintptr_t payload_len,
+                                  const uint8_t *n_ptr,
+                                  uintptr_t n_len,
+                                  const uint8_t *e_ptr,
+                                  uintptr_t e_len,
+                                  const uint8_t *d_ptr,
+                                  uintptr_t d_len,
+                                  const uint8_t *p_ptr,
+                                  uintptr_t p_len,
+                                  const uint8_t *q_ptr,
+                                  uintptr_t q_len,
+                                  uint8_t **out_sig_ptr,
+                                  uintptr_t *out_sig_len,
+                                  uintptr_t *out_sig_cap,
+                                  uint32_t hash_alg);
+
+/**
+ * Same as `sign_payload` but takes the private key as PKCS#8 or PKCS#1 DER
+ * instead of PEM.
+ */
+bool sign_payload_der(const uint8_t *payload_ptr,
+                      uintptr_t payload_len,
+                      const uint8_t *priv_key_ptr,
+                      uintptr_t priv_key_len,
+                      uint8_t **out_sig_ptr,
+                      uintptr_t *out_sig_len,
+                      uintptr_t *out_sig_cap,
+                      uint32_t hash_alg);
+
+/**
+ * Same as `sign_payload_ex` but takes a passphrase-encrypted PKCS#8 PEM
+ * (`BEGIN ENCRYPTED PRIVATE KEY`). Returns `CRYPTO_ERR_BAD_PASSPHRASE` for a
+ * wrong passphrase and `CRYPTO_ERR_KEY_PARSE` for a malformed PEM.
+ *
+ * The passphrase buffer is zeroized before returning, on success and failure
+ * alike, so callers must pass a copy they no longer need.
+ */
+int32_t sign_payload_encrypted(const uint8_t *payload_ptr,
+                               uintptr_t payload_len,
+                               const uint8_t *enc_priv_key_ptr,
+                               uintptr_t enc_priv_key_len,
+                               uint8_t *passphrase_ptr,
+                               uintptr_t passphrase_len,
+                               uint8_t **out_sig_ptr,
+                               uintptr_t *out_sig_len,
+                               uintptr_t *out_sig_cap,
+                               uint32_t hash_alg);
+
+/**
+ * Same as `sign_payload_ex` but signs on a PKCS#11 token (HSM) with the RSA
+ * private key named by the RFC 7512 URI `uri`, which must carry a
+ * `module-path` query attribute. The key never leaves the token. An empty PIN
+ * skips login. Returns `CRYPTO_ERR_BAD_PASSPHRASE` for a wrong PIN,
+ * `CRYPTO_ERR_TOKEN` for other token failures (including no or several
+ * matching keys) and `CRYPTO_ERR_NOT_SUPPORTED` in builds without the
+ * `pkcs11` feature.
+ *
+ * The PIN buffer is zeroized before returning, as in `sign_payload_encrypted`.
+ */
+int32_t sign_payload_pkcs11(const uint8_t *uri_ptr,
+                            uintptr_t uri_len,
+                            uint8_t *pin_ptr,
+                            uintptr_t pin_len,
+                            const uint8_t *payload_ptr,
+                            uintptr_t payload_len,
+                            uint8_t **out_sig_ptr,
+                            uintptr_t *out_sig_len,
+                            uintptr_t *out_sig_cap,
+                            uint32_t hash_alg);
+
+/**
+ * Encrypts a plaintext PKCS#8 or PKCS#1 PEM private key as encrypted PKCS#8
+ * PEM (`BEGIN ENCRYPTED PRIVATE KEY`, PBES2 / scrypt / AES-256-CBC), released
+ * with `free_signature`.
+ *
+ * Both the plaintext key buffer and the passphrase buffer are zeroized before
+ * returning, on success and failure alike, so callers must pass copies they no
+ * longer need.
+ */
+bool encrypt_private_key_pkcs8(uint8_t *priv_pem_ptr,
+                               uintptr_t priv_pem_len,
+                               uint8_t *passphrase_ptr,
+                               uintptr_t passphrase_len,
+                               uint8_t **out_ptr,
+                               uintptr_t *out_len,
+                               uintptr_t *out_cap);
+
+/**
+ * Inverse of `encrypt_private_key_pkcs8`: decrypts an encrypted PKCS#8 PEM and
+ * returns the key as plaintext PKCS#8 PEM, released with `free_signature`
+ * (which wipes it). The passphrase buffer is zeroized as in
+ * `sign_payload_encrypted`.
+ */
+bool decrypt_private_key_pkcs8(const uint8_t *enc_priv_key_ptr,
+                               uintptr_t enc_priv_key_len,
+                               uint8_t *passphrase_ptr,
+                               uintptr_t passphrase_len,
+                               uint8_t **out_ptr,
+                               uintptr_t *out_len,
+                               uintptr_t *out_cap);
+
+/**
+ * Same as `sign_payload` but returns the signature base64-encoded (standard
+ * alphabet, padded) as an ASCII buffer, released with `free_signature`.
+ */
+bool sign_payload_b64(const uint8_t *payload_ptr,
+                      uintptr_t payload_len,
+                      const uint8_t *priv_key_ptr,
+                      uintptr_t priv_key_len,
+                      uint8_t **out_ptr,
+                      uintptr_t *out_len,
+                      uintptr_t *out_cap,
+                      uint32_t hash_alg);
+
+/**
+ * Same as `verify_signature` but takes the signature base64-encoded (standard
+ * alphabet, padded). Invalid base64 fails verification.
+ */
+bool verify_signature_b64(const uint8_t *payload_ptr,
+                          uintptr_t payload_len,
+                          const uint8_t *b64_sig_ptr,
+                          uintptr_t b64_sig_len,
+                          const uint8_t *pub_key_ptr,
+                          uintptr_t pub_key_len,
+                          uint32_t hash_alg);
+
+/**
+ * RSASSA-PSS (SHA-256, MGF1-SHA-256) counterpart of `verify_signature`.
+ * `salt_len` must match the signer's; pass `PSS_DEFAULT_SALT_LEN` for the
+ * digest-length salt. A mismatched salt length simply fails verification.
+ */
+bool verify_signature_pss(const uint8_t *payload_ptr,
+                          uintptr_t payload_len,
+                          const uint8_t *sig_ptr,
+                          uintptr_t sig_len,
+                          const uint8_t *pub_key_ptr,
+                          uintptr_t pub_key_len,
+                          uintptr_t salt_len);
+
+/**
+ * Same as `verify_signature_pss` with the message digest and the MGF1 hash
+ * set separately, both `HASH_ALG_*` values, and the result as a `CRYPTO_*`
+ * code. A different `mgf_hash` is non-standard and only for interop with
+ * peers that sign that way, e.g. a SHA-256 digest with MGF1-SHA-1; equal
+ * hashes are ordinary PSS.
+ */
+int32_t verify_signature_pss_mgf(const uint8_t *payload_ptr,
+                                 uintptr_t payload_len,
+                                 const uint8_t *sig_ptr,
+                                 uintptr_t sig_len,
+                                 const uint8_t *pub_key_ptr,
+                                 uintptr_t pub_key_len,
+                                 uint32_t msg_hash,
+                                 uint32_t mgf_hash,
+                                 uintptr_t salt_len);
+
+/**
+ * RSASSA-PSS (SHA-256, MGF1-SHA-256) counterpart of `sign_payload`. The
+ * signature buffer is released with `free_signature`.
+ */
+bool sign_payload_pss(const uint8_t *payload_ptr,
+                      uintptr_t payload_len,
+                      const uint8_t *priv_key_ptr,
+                      uintptr_t priv_key_len,
+                      uint8_t **out_sig_ptr,
+                      uintptr_t *out_sig_len,
+                      uintptr_t *out_sig_cap,
+                      uintptr_t salt_len);
+
+/**
+ * Diagnostic: returns the salt length a PSS signature was made with (MGF1
+ * using `hash_alg`, as `hash_alg` itself), or -1 if the signature does not
+ * decode under the PEM public key. The payload is not checked; use
+ * `verify_signature_pss` for that.
+ */
+intptr_t pss_detect_salt_len(const uint8_t *sig_ptr,
+                             uintptr_t sig_len,
+                             const uint8_t *pub_key_ptr,
+                             uintptr_t pub_key_len,
+                             uint32_t hash_alg);
+
+/**
+ * Client step of an RFC 9474 RSA blind signature
+ * (RSABSSA-SHA384-PSS-Deterministic; see `api::rsabssa_blind`). Writes the
+ * blinded message to send to the signer and the secret blinding state to
+ * pass to `rsabssa_finalize`. Both are released with `free_signature`, which
+ * zeroizes the state.
+ */
+int32_t rsabssa_blind(const uint8_t *payload_ptr,
+                      uintptr_t payload_len,
+                      const uint8_t *pub_key_ptr,
+                      uintptr_t pub_key_len,
+                      uint8_t **out_blinded_ptr,
+                      uintptr_t *out_blinded_len,
+                      uintptr_t *out_blinded_cap,
+                      uint8_t **out_state_ptr,
+                      uintptr_t *out_state_len,
+                      uintptr_t *out_state_cap);
+
+/**
+ * Signer step of an RFC 9474 RSA blind signature: signs the blinded message
+ * from `rsabssa_blind` with the PEM private key, without learning the
+ * message. The blind signature is released with `free_signature`.
+ */
+int32_t rsabssa_blind_sign(const uint8_t *blinded_ptr,
+                           uintptr_t blinded_len,
+                           const uint8_t *priv_key_ptr,
+                           uintptr_t priv_key_len,
+                           uint8_t **out_ptr,
+                           uintptr_t *out_len,
+                           uintptr_t *out_cap);
+
+/**
+ * Client step of an RFC 9474 RSA blind signature: unblinds `blind_sig` with
+ * the state from `rsabssa_blind` into an RSASSA-PSS signature (SHA-384,
+ * MGF1-SHA-384, 48-byte salt) over the original payload, which is verified
+ * before it is written. Returns `CRYPTO_ERR_SIGNATURE_MISMATCH` if it does
+ * not verify. The signature is released with `free_signature`.
+ */
+int32_t rsabssa_finalize(const uint8_t *blind_sig_ptr,
+                         uintptr_t blind_sig_len,
+                         const uint8_t *state_ptr,
+                         uintptr_t state_len,
+                         const uint8_t *pub_key_ptr,
+                         uintptr_t pub_key_len,
+                         uint8_t **out_sig_ptr,
+                         uintptr_t *out_sig_len,
+                         uintptr_t *out_sig_cap);
+
+/**
+ * Encrypts `payload` to the PEM public key with RSA-OAEP (SHA-256). Fails if
+ * the payload exceeds the OAEP limit of `k - 66` bytes for a `k`-byte modulus.
+ * The ciphertext is released with `free_signature`.
+ */
+bool rsa_encrypt_oaep(const uint8_t *payload_ptr,
+                      uintptr_t payload_len,
+                      const uint8_t *pub_key_ptr,
+                      uintptr_t pub_key_len,
+                      uint8_t **out_ptr,
+                      uintptr_t *out_len,
+                      uintptr_t *out_cap);
+
+/**
+ * Decrypts an RSA-OAEP (SHA-256) ciphertext with the PEM private key. The
+ * plaintext is released with `free_signature`.
+ */
+bool rsa_decrypt_oaep(const uint8_t *ciphertext_ptr,
+                      uintptr_t ciphertext_len,
+                      const uint8_t *priv_key_ptr,
+                      uintptr_t priv_key_len,
+                      uint8_t **out_ptr,
+                      uintptr_t *out_len,
+                      uintptr_t *out_cap);
+
+/**
+ * Encrypts `plaintext` to the PEM public key with RSA PKCS#1 v1.5 padding,
+ * for peers that cannot do OAEP. Fails if the plaintext exceeds `k - 11`
+ * bytes. The ciphertext is released with `free_signature`.
+ */
+bool rsa_encrypt_pkcs1v15(const uint8_t *plaintext_ptr,
+                          uintptr_t plaintext_len,
+                          const uint8_t *pub_key_ptr,
+                          uintptr_t pub_key_len,
+                          uint8_t **out_ptr,
+                          uintptr_t *out_len,
+                          uintptr_t *out_cap);
+
+/**
+ * Decrypts an RSA PKCS#1 v1.5 ciphertext with the PEM private key. The
+ * plaintext is released with `free_signature`.
+ *
+ * PKCS#1 v1.5 decryption is open to Bleichenbacher-style padding oracles.
+ * Decryption is blinded and the crate's unpadding is constant time, but the
+ * result is not: a padding failure returns false and a success returns a
+ * plaintext of the decoded length, so callers must not let a remote peer
+ * tell either apart. `rsa` does not implement implicit rejection, and its
+ * big-integer arithmetic is not guaranteed constant time (RUSTSEC-2023-0071).
+ * Where the plaintext length is known in advance, use
+ * `decrypt_pkcs1v15_ct`; prefer `rsa_decrypt_oaep` wherever the peer allows
+ * it.
+ */
+bool rsa_decrypt_pkcs1v15(const uint8_t *ciphertext_ptr,
+                          uintptr_t ciphertext_len,
+                          const uint8_t *priv_key_ptr,
+                          uintptr_t priv_key_len,
+                          uint8_t **out_ptr,
+                          uintptr_t *out_len,
+                          uintptr_t *out_cap);
+
+/**
+ * Decrypts an RSA PKCS#1 v1.5 ciphertext whose plaintext must be exactly
+ * `expected_len` bytes (a TLS-style premaster secret or wrapped key) without
+ * exposing whether the padding was valid. Exactly `expected_len` bytes are
+ * always written to `out_ptr`: the plaintext when the padding is valid and
+ * the message has that length, random bytes otherwise, with `*out_ok` set
+ * accordingly. The padding check and the selection are constant time and
+ * the return value is `CRYPTO_OK` either way; the caller should carry on
+ * with the output and read `*out_ok` only where that cannot leak, so a bad
+ * ciphertext surfaces later as an ordinary key mismatch.
+ *
+ * Errors are reported only for public inputs: null pointers, an unparseable
+ * key, a ciphertext that is not the modulus size, or an `expected_len` over
+ * `k - 11`. The RSA operation is blinded, but `rsa`'s big-integer arithmetic
+ * is not guaranteed constant time (RUSTSEC-2023-0071).
+ */
+int32_t decrypt_pkcs1v15_ct(const uint8_t *ciphertext_ptr,
+                            uintptr_t ciphertext_len,
+                            const uint8_t *priv_key_ptr,
+                            uintptr_t priv_key_len,
+                            uint8_t *out_ptr,
+                            uintptr_t expected_len,
+                            bool *out_ok);
+
+/**
+ * Encrypts a plaintext longer than one RSA block by splitting it into
+ * maximum-size OAEP (SHA-256) chunks and concatenating the `k`-byte
+ * ciphertext blocks. This is a convenience for small blobs, not a substitute
+ * for hybrid encryption (RSA-wrapped AES-GCM key) for large data. The
+ * ciphertext is released with `free_signature`.
+ */
+bool rsa_encrypt_chunked(const uint8_t *plaintext_ptr,
+                         uintptr_t plaintext_len,
+                         const uint8_t *pub_key_ptr,
+                         uintptr_t pub_key_len,
+                         uint8_t **out_ptr,
+                         uintptr_t *out_len,
+                         uintptr_t *out_cap);
+
+/**
+ * Decrypts the output of `rsa_encrypt_chunked`, splitting it on the modulus
+ * size. The plaintext is released with `free_signature`.
+ */
+bool rsa_decrypt_chunked(const uint8_t *ciphertext_ptr,
+                         uintptr_t ciphertext_len,
+                         const uint8_t *priv_key_ptr,
+                         uintptr_t priv_key_len,
+                         uint8_t **out_ptr,
+                         uintptr_t *out_len,
+                         uintptr_t *out_cap);
+
+/**
+ * Returns the largest plaintext, in bytes, that one RSA encryption with the
+ * PEM public key can take: `k - 2*hLen - 2` for OAEP-SHA256 (`hLen` = 32, so
+ * `k - 66`) and `k - 11` for PKCS#1 v1.5, where `k` is the modulus size in
+ * bytes. Returns -1 if the key does not parse or `padding` is unknown.
+ */
+intptr_t rsa_max_message_len(const uint8_t *pub_key_ptr, uintptr_t pub_key_len, int32_t padding);
+
+/**
+ * Computes an HMAC-SHA256 tag over `payload` with a shared secret key. The
+ * 32-byte tag is released with `free_signature`.
+ */
+bool hmac_sign(const uint8_t *payload_ptr,
+               uintptr_t payload_len,
+               const uint8_t *key_ptr,
+               uintptr_t key_len,
+               uint8_t **out_ptr,
+               uintptr_t *out_len,
+               uintptr_t *out_cap);
+
+/**
+ * Checks an HMAC-SHA256 tag. The comparison is constant-time.
+ */
+bool hmac_verify(const uint8_t *payload_ptr,
+                 uintptr_t payload_len,
+                 const uint8_t *sig_ptr,
+                 uintptr_t sig_len,
+                 const uint8_t *key_ptr,
+                 uintptr_t key_len);
+
+/**
+ * Compares two buffers in constant time. Buffers of different lengths compare
+ * unequal immediately: only the contents are treated as secret, not the length.
+ */
+bool constant_time_eq(const uint8_t *a_ptr, uintptr_t a_len, const uint8_t *b_ptr, uintptr_t b_len);
+
+/**
+ * Derives `out_len` bytes with HKDF-SHA256 (RFC 5869) into `out`. `salt` and
+ * `info` may be null when their length is 0; an empty salt is equivalent to
+ * a zero-filled one. Fails if `out_len` exceeds 255 * 32 bytes.
+ */
+bool hkdf_sha256(const uint8_t *ikm_ptr,
+                 uintptr_t ikm_len,
+                 const uint8_t *salt_ptr,
+                 uintptr_t salt_len,
+                 const uint8_t *info_ptr,
+                 uintptr_t info_len,
+                 uint8_t *out,
+                 uintptr_t out_len);
+
+/**
+ * Derives `out_len` bytes from a password with PBKDF2-HMAC-SHA256 into `out`.
+ * `iterations` must be non-zero. The password is only borrowed, never copied,
+ * so wiping it afterwards is up to the caller.
+ */
+bool pbkdf2_hmac_sha256(const uint8_t *password_ptr,
+                        uintptr_t password_len,
+                        const uint8_t *salt_ptr,
+                        uintptr_t salt_len,
+                        uint32_t iterations,
+                        uint8_t *out,
+                        uintptr_t out_len);
+
+/**
+ * Hashes a password with Argon2id and a random salt, writing the PHC string
+ * (e.g. `$argon2id$v=19$m=19456,t=2,p=1$...`) as an ASCII buffer released
+ * with `free_signature`. The password buffer is zeroized before returning.
+ * If Argon2 itself fails the status is `CRYPTO_ERR_PASSWORD_HASH` and
+ * `last_error_message` carries its message.
+ */
+bool argon2_hash(uint8_t *password_ptr,
+                 uintptr_t password_len,
+                 uint8_t **out_ptr,
+                 uintptr_t *out_len,
+                 uintptr_t *out_cap);
+
+/**
+ * Checks a password against a PHC string from `argon2_hash`. Returns
+ * `CRYPTO_ERR_PASSWORD_MISMATCH` for a wrong password and
+ * `CRYPTO_ERR_MALFORMED_TOKEN` for a hash string that cannot be used. The
+ * password buffer is zeroized before returning.
+ */
+int32_t argon2_verify(uint8_t *password_ptr,
+                      uintptr_t password_len,
+                      const uint8_t *phc_ptr,
+                      uintptr_t phc_len);
+
+/**
+ * Encrypts `plaintext` with AES-256-GCM under a 32-byte key and 12-byte nonce,
+ * authenticating `aad` as well (`aad_ptr` may be null when `aad_len` is 0).
+ * The output is the ciphertext followed by the 16-byte tag, released with
+ * `free_signature`. Wrong key or nonce lengths return
+ * `CRYPTO_ERR_INVALID_LENGTH`; a plaintext beyond GCM's length limit returns
+ * `CRYPTO_ERR_AEAD`.
+ */
+int32_t aes_gcm_encrypt(const uint8_t *plaintext_ptr,
+                        uintptr_t plaintext_len,
+                        const uint8_t *key_ptr,
+                        uintptr_t key_len,
+                        const uint8_t *nonce_ptr,
+                        uintptr_t nonce_len,
+                        const uint8_t *aad_ptr,
+                        uintptr_t aad_len,
+                        uint8_t **out_ptr,
+                        uintptr_t *out_len,
+                        uintptr_t *out_cap);
+
+/**
+ * Decrypts `ciphertext || tag` produced by `aes_gcm_encrypt`. Returns
+ * `CRYPTO_ERR_TAG_MISMATCH` if the ciphertext, tag or AAD were tampered with;
+ * nothing is written in that case. The plaintext is released with
+ * `free_signature`.
+ */
+int32_t aes_gcm_decrypt(const uint8_t *ciphertext_ptr,
+                        uintptr_t ciphertext_len,
+                        const uint8_t *key_ptr,
+                        uintptr_t key_len,
+                        const uint8_t *nonce_ptr,
+                        uintptr_t nonce_len,
+                        const uint8_t *aad_ptr,
+                        uintptr_t aad_len,
+                        uint8_t **out_ptr,
+                        uintptr_t *out_len,
+                        uintptr_t *out_cap);
+
+/**
+ * Starts streaming AES-256-GCM encryption under a 32-byte key and a 7-byte
+ * nonce prefix (`AES_GCM_STREAM_NONCE_LEN`), authenticating `aad` (may be
+ * null when `aad_len` is 0) with every segment. Uses the STREAM
+ * construction: the input is sealed in 64 KiB segments, each under the
+ * prefix plus a segment counter and a last-segment flag, so nonces never
+ * repeat within a stream and truncation or reordering is detected. The same
+ * key and prefix must never be used for two streams. The output is not
+ * compatible with `aes_gcm_decrypt`. Returns null on bad input;
+ * `last_error_message` has the reason.
+ */
+struct AeadCtx *aes_gcm_enc_new(const uint8_t *key_ptr,
+                                uintptr_t key_len,
+                                const uint8_t *nonce_ptr,
+                                uintptr_t nonce_len,
+                                const uint8_t *aad_ptr,
+                                uintptr_t aad_len);
+
+/**
+ * Starts streaming decryption of output from `aes_gcm_enc_new`, with the
+ * same key, nonce prefix and AAD.
+ */
+struct AeadCtx *aes_gcm_dec_new(const uint8_t *key_ptr,
+                                uintptr_t key_len,
+                                const uint8_t *nonce_ptr,
+                                uintptr_t nonce_len,
+                                const uint8_t *aad_ptr,
+                                uintptr_t aad_len);
+
+/**
+ * Feeds plaintext into an encryption context. Any ciphertext completed so
+ * far (possibly none) is written to the out-params and released with
+ * `free_signature`; concatenate every chunk in order.
+ */
+int32_t aes_gcm_enc_update(struct AeadCtx *ctx,
+                           const uint8_t *data_ptr,
+                           uintptr_t data_len,
+                           uint8_t **out_ptr,
+                           uintptr_t *out_len,
+                           uintptr_t *out_cap);
+
+/**
+ * Ends encryption: writes the remaining ciphertext to the out-params and the
+ * 16-byte tag to `out_tag`, which the receiver passes to
+ * `aes_gcm_dec_finalize`. The context cannot be used again but must still be
+ * freed.
+ */
+int32_t aes_gcm_enc_finalize(struct AeadCtx *ctx,
+                             uint8_t **out_ptr,
+                             uintptr_t *out_len,
+                             uintptr_t *out_cap,
+                             uint8_t *out_tag);
+
+/**
+ * Feeds ciphertext into a decryption context and writes any plaintext from
+ * completed segments, released with `free_signature`. Each segment is
+ * authenticated before it is released, but the stream is only known to be
+ * complete once `aes_gcm_dec_finalize` returns `CRYPTO_OK`, so do not act on
+ * the plaintext before then. A tampered segment returns
+ * `CRYPTO_ERR_TAG_MISMATCH`.
+ */
+int32_t aes_gcm_dec_update(struct AeadCtx *ctx,
+                           const uint8_t *data_ptr,
+                           uintptr_t data_len,
+                           uint8_t **out_ptr,
+                           uintptr_t *out_len,
+                           uintptr_t *out_cap);
+
+/**
+ * Ends decryption, verifying the final segment against the 16-byte `tag`
+ * from `aes_gcm_enc_finalize` and writing its plaintext. Returns
+ * `CRYPTO_ERR_TAG_MISMATCH`, writing nothing, if the stream was tampered
+ * with or truncated.
+ */
+int32_t aes_gcm_dec_finalize(struct AeadCtx *ctx,
+                             const uint8_t *tag_ptr,
+                             uint8_t **out_ptr,
+                             uintptr_t *out_len,
+                             uintptr_t *out_cap);
+
+void aes_gcm_ctx_free(struct AeadCtx *ctx);
+
+/**
+ * Fills `out` with `len` bytes from the operating system CSPRNG, the same
+ * source used for key generation and signing. Returns false if `out` is null
+ * or the OS RNG fails.
+ */
+bool generate_random_bytes(uint8_t *out, uintptr_t len);
+
+/**
+ * Generates a fresh RSA key pair, returning the private key as PKCS#8 PEM and
+ * the public key as SPKI PEM. Both buffers are released with `free_signature`.
+ * `bits` must be between `RSA_MIN_GENERATED_BITS` and `RSA_MAX_MODULUS_BITS`.
+ */
+bool generate_rsa_keypair(uintptr_t bits,
+                          uint8_t **out_priv_ptr,
+                          uintptr_t *out_priv_len,
+                          uintptr_t *out_priv_cap,
+                          uint8_t **out_pub_ptr,
+                          uintptr_t *out_pub_len,
+                          uintptr_t *out_pub_cap);
+
+/**
+ * Generates a fresh Ed25519 key pair, returning the private key as PKCS#8 PEM
+ * and the public key as SPKI PEM, in the same shape as
+ * `generate_rsa_keypair`. Both buffers are released with `free_signature`.
+ */
+bool generate_ed25519_keypair(uint8_t **out_priv_ptr,
+                              uintptr_t *out_priv_len,
+                              uintptr_t *out_priv_cap,
+                              uint8_t **out_pub_ptr,
+                              uintptr_t *out_pub_len,
+                              uintptr_t *out_pub_cap);
+
+/**
+ * Derives the public half of a PKCS#8 or PKCS#1 PEM private key and returns it
+ * as SPKI PEM, released with `free_signature`.
+ */
+bool public_key_from_private(const uint8_t *priv_key_ptr,
+                             uintptr_t priv_key_len,
+                             uint8_t **out_ptr,
+                             uintptr_t *out_len,
+                             uintptr_t *out_cap);
+
+/**
+ * Re-encodes an SPKI or PKCS#1 PEM public key as DER SPKI. The buffer is
+ * released with `free_signature`.
+ */
+bool pem_to_der_public_key(const uint8_t *pem_ptr,
+                           uintptr_t pem_len,
+                           uint8_t **out_ptr,
+                           uintptr_t *out_len,
+                           uintptr_t *out_cap);
+
+/**
+ * Re-encodes a DER SPKI public key as SPKI PEM.
+ */
+bool der_to_pem_public_key(const uint8_t *der_ptr,
+                           uintptr_t der_len,
+                           uint8_t **out_ptr,
+                           uintptr_t *out_len,
+                           uintptr_t *out_cap);
+
+/**
+ * Re-encodes a PKCS#8 or PKCS#1 PEM private key as PKCS#8 DER.
+ */
+bool pem_to_der_private_key(const uint8_t *pem_ptr,
+                            uintptr_t pem_len,
+                            uint8_t **out_ptr,
+                            uintptr_t *out_len,
+                            uintptr_t *out_cap);
+
+/**
+ * Re-encodes a PKCS#8 or PKCS#1 DER private key as PKCS#8 PEM.
+ */
+bool der_to_pem_private_key(const uint8_t *der_ptr,
+                            uintptr_t der_len,
+                            uint8_t **out_ptr,
+                            uintptr_t *out_len,
+                            uintptr_t *out_cap);
+
+/**
+ * Re-encodes a PKCS#8 or PKCS#1 PEM private key as PKCS#8 PEM
+ * (`BEGIN PRIVATE KEY`). The intermediate encodings are zeroized; the output
+ * is released with `free_signature`, which zeroizes it too.
+ */
+bool convert_private_key_to_pkcs8(const uint8_t *priv_pem_ptr,
+                                  uintptr_t priv_pem_len,
+                                  uint8_t **out_ptr,
+                                  uintptr_t *out_len,
+                                  uintptr_t *out_cap);
+
+/**
+ * Re-encodes a PKCS#8 or PKCS#1 PEM private key as PKCS#1 PEM
+ * (`BEGIN RSA PRIVATE KEY`), zeroizing as `convert_private_key_to_pkcs8`
+ * does.
+ */
+bool convert_private_key_to_pkcs1(const uint8_t *priv_pem_ptr,
+                                  uintptr_t priv_pem_len,
+                                  uint8_t **out_ptr,
+                                  uintptr_t *out_len,
+                                  uintptr_t *out_cap);
+
+/**
+ * Re-emits a PEM public key in canonical form (SPKI, 64-column lines, LF,
+ * single trailing newline), accepting any line length, CRLF and stray
+ * whitespace on input. Input that does not decode to a valid key fails.
+ */
+bool canonicalize_public_key_pem(const uint8_t *pem_ptr,
+                                 uintptr_t pem_len,
+                                 uint8_t **out_ptr,
+                                 uintptr_t *out_len,
+                                 uintptr_t *out_cap);
+
+/**
+ * Same as `canonicalize_public_key_pem` for a PKCS#8 or PKCS#1 private key,
+ * emitting PKCS#8 PEM.
+ */
+bool canonicalize_private_key_pem(const uint8_t *pem_ptr,
+                                  uintptr_t pem_len,
+                                  uint8_t **out_ptr,
+                                  uintptr_t *out_len,
+                                  uintptr_t *out_cap);
+
+/**
+ * Splits a PEM document into its label and decoded DER body without parsing
+ * the body, so it works for certificates, CSRs or anything else. Both
+ * buffers are released with `free_signature`. Returns false, writing
+ * nothing, if the armor or base64 is malformed.
+ */
+bool pem_decode(const uint8_t *pem_ptr,
+                uintptr_t pem_len,
+                uint8_t **out_label_ptr,
+                uintptr_t *out_label_len,
+                uintptr_t *out_label_cap,
+                uint8_t **out_der_ptr,
+                uintptr_t *out_der_len,
+                uintptr_t *out_der_cap);
+
+/**
+ * Returns how many PEM documents a bundle of concatenated PEM blocks (a key
+ * plus a certificate chain, say) holds, for use with `pem_block_at`. Text
+ * between blocks is ignored. Returns -1 if `bundle` is null or any block is
+ * unterminated or fails to decode, so every index below the count is
+ * readable. A bundle with no PEM blocks has a count of 0.
+ */
+intptr_t pem_block_count(const uint8_t *bundle_ptr, uintptr_t bundle_len);
+
+/**
+ * Same as `pem_decode` for the `index`th PEM block (from 0) of a bundle, as
+ * counted by `pem_block_count`. Both buffers are released with
+ * `free_signature`. Returns false, writing nothing, if `index` is out of
+ * range, any block in the bundle is unterminated, or that block fails to
+ * decode.
+ */
+bool pem_block_at(const uint8_t *bundle_ptr,
+                  uintptr_t bundle_len,
+                  uintptr_t index,
+                  uint8_t **out_label_ptr,
+                  uintptr_t *out_label_len,
+                  uintptr_t *out_label_cap,
+                  uint8_t **out_der_ptr,
+                  uintptr_t *out_der_len,
+                  uintptr_t *out_der_cap);
+
+/**
+ * Wraps DER bytes in PEM armor with the given label (UTF-8, not
+ * NUL-terminated, without the `BEGIN`/`END` words). Lines are 64 columns with
+ * LF endings. The output is released with `free_signature`.
+ */
+bool pem_encode(const uint8_t *label_ptr,
+                uintptr_t label_len,
+                const uint8_t *der_ptr,
+                uintptr_t der_len,
+                uint8_t **out_ptr,
+                uintptr_t *out_len,
+                uintptr_t *out_cap);
+
+/**
+ * Caps the payload length, in bytes, accepted by every sign and verify
+ * function; longer payloads are rejected before any hashing with
+ * `CRYPTO_ERR_PAYLOAD_TOO_LARGE` (or false). Multi-part inputs (iovec
+ * segments, JWS header plus claims, COSE message plus detached payload) count
+ * their total; `verify_batch` fails if any one payload is over. Prehashed,
+ * streaming context and file-based functions never buffer the payload and are
+ * not limited. 0, the default, means unlimited.
+ */
+void set_max_payload_len(uintptr_t max_len);
+
+/**
+ * Sets the key policy enforced by `verify_signature`, `verify_signature_ex`
+ * (and the functions built on it, such as the certificate and JWK variants)
+ * and `verify_with_handle`. Keys with a modulus shorter than `min_key_bits`,
+ * or with an exponent other than 65537 when `require_e_65537` is set, are
+ * rejected with `CRYPTO_ERR_POLICY_VIOLATION` (or false) before any
+ * signature check. The default, `(0, false)`, accepts every key.
+ */
+void set_verify_policy(uint32_t min_key_bits, bool require_e_65537);
+
+/**
+ * Enables the parsed public key cache used by every function taking a PEM
+ * public key, holding up to `capacity` keys (least recently used evicted
+ * first). A capacity of 0 disables and empties the cache; it starts disabled.
+ */
+void set_key_cache_capacity(uintptr_t capacity);
+
+/**
+ * Drops every cached public key, keeping the configured capacity.
+ */
+void clear_key_cache(void);
+
+/**
+ * Reports cumulative public key cache hits and misses since process start.
+ */
+bool key_cache_stats(uint64_t *out_hits, uint64_t *out_misses);
+
+/**
+ * Parses an SPKI or PKCS#1 PEM public key once. Returns null on failure;
+ * release the handle with `free_public_key_handle`.
+ */
+struct PublicKeyHandle *load_public_key(const uint8_t *pub_key_ptr, uintptr_t pub_key_len);
+
+/**
+ * Parses a PKCS#8 or PKCS#1 PEM private key once. Returns null on failure;
+ * release the handle with `free_private_key_handle`.
+ */
+struct PrivateKeyHandle *load_private_key(const uint8_t *priv_key_ptr, uintptr_t priv_key_len);
+
+bool verify_with_handle(const struct PublicKeyHandle *handle,
+                        const uint8_t *payload_ptr,
+                        uintptr_t payload_len,
+                        const uint8_t *sig_ptr,
+                        uintptr_t sig_len,
+                        uint32_t hash_alg);
+
+bool sign_with_handle(const struct PrivateKeyHandle *handle,
+                      const uint8_t *payload_ptr,
+                      uintptr_t payload_len,
+                      uint8_t **out_sig_ptr,
+                      uintptr_t *out_sig_len,
+                      uintptr_t *out_sig_cap,
+                      uint32_t hash_alg);
+
+void free_public_key_handle(struct PublicKeyHandle *handle);
+
+void free_private_key_handle(struct PrivateKeyHandle *handle);
+
+/**
+ * Reports which encoding an RSA public key arrived in as one of the
+ * `PUBKEY_FORMAT_*` values, or -1 if it is none of them. Only probes the
+ * encoding; the key is not otherwise validated and no memory is allocated.
+ */
+int32_t detect_public_key_format(const uint8_t *pub_key_ptr, uintptr_t pub_key_len);
+
+/**
+ * Parses each of `count` public keys, for validating a whole keyring at
+ * startup. A key may be PEM, DER or an RSA JWK (JSON object). The status of
+ * `keys[i]` is written to `out_status[i]`: `CRYPTO_OK`, or the negative
+ * `CRYPTO_ERR_*` code the key failed with. Returns the number of keys that
+ * parsed; 0 if `keys` or `out_status` is null. PEM keys that parse are kept
+ * in the key cache, so the first verification with them does not re-parse.
+ * The thread's last error is not touched.
+ */
+uintptr_t load_keyring(const struct Slice *keys, uintptr_t count, int32_t *out_status);
+
+/**
+ * Writes the SHA-256 fingerprint of a PEM or DER public key into `out` (32
+ * bytes). The key is re-encoded as canonical DER SPKI before hashing, so PEM,
+ * DER and PKCS#1 forms of the same key all produce the same fingerprint.
+ */
+bool public_key_fingerprint(const uint8_t *pub_key_ptr, uintptr_t pub_key_len, uint8_t *out);
+
+/**
+ * Returns the RFC 7638 JWK thumbprint of a PEM or DER public key as a
+ * base64url string (no padding, 43 characters), for use as a JWKS `kid`
+ * that clients can recompute. Released with `free_signature`.
+ */
+bool rsa_jwk_thumbprint(const uint8_t *pub_key_ptr,
+                        uintptr_t pub_key_len,
+                        uint8_t **out_ptr,
+                        uintptr_t *out_len,
+                        uintptr_t *out_cap);
+
+/**
+ * Writes a PEM or DER public key as JWK JSON for a JWKS endpoint:
+ * `{"kty":"RSA","n":...,"e":...,"alg":"RS256","use":"sig","kid":...}`. `kid`
+ * must be UTF-8 and is JSON-escaped; a null `kid_ptr` omits the member.
+ * Released with `free_signature`.
+ */
+bool rsa_public_key_to_jwk(const uint8_t *pub_key_ptr,
+                           uintptr_t pub_key_len,
+                           const uint8_t *kid_ptr,
+                           uintptr_t kid_len,
+                           uint8_t **out_ptr,
+                           uintptr_t *out_len,
+                           uintptr_t *out_cap);
+
+/**
+ * Verifies a PKCS#1 v1.5 signature against an RSA public key given as JWK
+ * JSON (`kty`, `n`, `e`; other members such as `alg` and `kid` are ignored).
+ * Returns `CRYPTO_ERR_INVALID_JSON` if the JSON does not parse and
+ * `CRYPTO_ERR_INVALID_JWK` if `kty` is not `RSA` or `n`/`e` are missing or not
+ * unpadded base64url. Other codes match `verify_signature_ex`.
+ */
+int32_t verify_signature_jwk(const uint8_t *payload_ptr,
+                             uintptr_t payload_len,
+                             const uint8_t *sig_ptr,
+                             uintptr_t sig_len,
+                             const uint8_t *jwk_json_ptr,
+                             uintptr_t jwk_json_len,
+                             uint32_t hash_alg);
+
+/**
+ * Verifies a PKCS#1 v1.5 signature against an OpenSSH-format RSA public key
+ * (`ssh-rsa AAAA... [comment]`, one `authorized_keys` line without options).
+ * A key of another type, such as `ssh-ed25519`, returns
+ * `CRYPTO_ERR_UNSUPPORTED_ALG`; a line that does not parse returns
+ * `CRYPTO_ERR_KEY_PARSE`. Other codes match `verify_signature_ex`.
+ */
+int32_t verify_signature_openssh(const uint8_t *payload_ptr,
+                                 uintptr_t payload_len,
+                                 const uint8_t *sig_ptr,
+                                 uintptr_t sig_len,
+                                 const uint8_t *ssh_key_ptr,
+                                 uintptr_t ssh_key_len,
+                                 uint32_t hash_alg);
+
+/**
+ * Verifies an OpenPGP detached signature (`gpg --detach-sign`, binary or
+ * armored) over the payload with an OpenPGP public key (`gpg --export`,
+ * binary or armored). The hash algorithm comes from the signature packet.
+ * Returns `CRYPTO_ERR_NOT_SUPPORTED` for signature packet versions other than
+ * 3 and 4, non-document signatures, or in builds without the `pgp` feature;
+ * `CRYPTO_ERR_UNSUPPORTED_ALG` for non-RSA signatures or unsupported digests;
+ * `CRYPTO_ERR_MALFORMED_TOKEN` if the signature does not parse and
+ * `CRYPTO_ERR_KEY_PARSE` if the key does not parse, fails its
+ * self-signatures or has no RSA key matching the issuer. Key expiry and
+ * revocation are not checked.
+ */
+int32_t verify_openpgp_detached(const uint8_t *payload_ptr,
+                                uintptr_t payload_len,
+                                const uint8_t *sig_packet_ptr,
+                                uintptr_t sig_packet_len,
+                                const uint8_t *pub_key_ptr,
+                                uintptr_t pub_key_len);
+
+/**
+ * Signs like `sign_payload_ex` but writes the signature into a caller-owned
+ * buffer. If `out_buf_cap` is too small, nothing is signed, `out_written` is
+ * set to the required length and `CRYPTO_ERR_BUFFER_TOO_SMALL` is returned.
+ */
+int32_t sign_payload_into(const uint8_t *payload_ptr,
+                          uintptr_t payload_len,
+                          const uint8_t *priv_key_ptr,
+                          uintptr_t priv_key_len,
+                          uint8_t *out_buf,
+                          uintptr_t out_buf_cap,
+                          uintptr_t *out_written,
+                          uint32_t hash_alg);
+
+/**
+ * Returns the signature length (the modulus size in bytes) for a PEM private
+ * key, or 0 if it cannot be parsed.
+ */
+uintptr_t signature_len(const uint8_t *priv_key_ptr, uintptr_t priv_key_len);
+
+/**
+ * Returns the signature length in bytes for `scheme` without needing a key,
+ * so a buffer for the `_into` functions can be allocated once:
+ *
+ * - RSA PKCS#1 v1.5 and PSS: `curve_or_bits` is the modulus size in bits and
+ *   the length is `ceil(bits / 8)`.
+ * - ECDSA: `curve_or_bits` is an `ECDSA_CURVE_*` value. With `der` false the
+ *   length is the fixed `r || s` width, twice the field size (P-256 64,
+ *   P-384 96, P-521 132). With `der` true it is the largest DER encoding
+ *   (72, 104, 141); real signatures may be shorter.
+ * - Ed25519: always 64; `curve_or_bits` is ignored.
+ *
+ * `der` only applies to ECDSA. Returns -1 for an unknown scheme or curve, a
+ * modulus of 0 or more than 8192 bits, or `der` with a non-ECDSA scheme.
+ */
+intptr_t expected_signature_len(int32_t scheme, uint32_t curve_or_bits, bool der);
+
+/**
+ * Classifies a signature scheme for policy warnings: `DEPRECATION_OK`,
+ * `DEPRECATION_DEPRECATED` (still works, warn) or `DEPRECATION_FORBIDDEN`.
+ * The thresholds are kept in one table, `api::deprecation_status`; a small
+ * RSA modulus is forbidden, a modest one or SHA-1 is deprecated, and ECDSA
+ * and Ed25519 are ok. `key_bits` is the RSA modulus or ECDSA field size (256,
+ * 384 or 521) and is ignored for Ed25519; `hash_alg` only affects RSA but
+ * must be a valid `HASH_ALG_*` value. Returns `CRYPTO_ERR_UNSUPPORTED_ALG`
+ * for an unknown hash and `CRYPTO_ERR_INVALID_ARGUMENT` for an unknown
+ * scheme or ECDSA size.
+ */
+int32_t algorithm_deprecation_status(int32_t scheme, uint32_t hash_alg, uint32_t key_bits);
+
+/**
+ * Returns the modulus size in bits of a PEM public key, or 0 if it cannot be
+ * parsed.
+ */
+uint32_t rsa_key_bits(const uint8_t *pub_key_ptr, uintptr_t pub_key_len);
+
+/**
+ * Screens a PEM public key against `blocklist` (UTF-8 text, one entry per
+ * line: known-bad primes or moduli in hex, or Debian openssl-blacklist
+ * fingerprints; see `api::rsa_modulus_is_weak`). Returns 1 if the modulus
+ * matches an entry, 0 if it passes, or a negative `CRYPTO_ERR_*` code if the
+ * key or blocklist cannot be parsed. The key parser already rejects an even
+ * modulus, so such a key returns `CRYPTO_ERR_KEY_PARSE`. This is a heuristic
+ * screen, not a factoring attempt; passing it does not make a key strong.
+ */
+int32_t rsa_key_is_weak(const uint8_t *pub_key_ptr,
+                        uintptr_t pub_key_len,
+                        const uint8_t *blocklist_ptr,
+                        uintptr_t blocklist_len);
+
+/**
+ * Writes the public exponent of a PEM public key as minimal big-endian bytes
+ * (65537 is `01 00 01`), for enrollment checks that reject small exponents.
+ * Returns false if the key cannot be parsed.
+ */
+bool rsa_public_exponent(const uint8_t *pub_key_ptr,
+                         uintptr_t pub_key_len,
+                         uint8_t **out_ptr,
+                         uintptr_t *out_len,
+                         uintptr_t *out_cap);
+
+/**
+ * Same as `rsa_public_exponent` as an integer. Returns 0 if the key cannot be
+ * parsed or the exponent does not fit in 64 bits.
+ */
+uint64_t rsa_public_exponent_u64(const uint8_t *pub_key_ptr, uintptr_t pub_key_len);
+
+/**
+ * Same as `rsa_key_bits` for a PEM private key.
+ */
+uint32_t rsa_private_key_bits(const uint8_t *priv_key_ptr, uintptr_t priv_key_len);
+
+/**
+ * Checks that a PEM private key and PEM public key belong together by
+ * comparing modulus and public exponent. Returns `CRYPTO_OK` on a match,
+ * `KEYS_MISMATCH` if they differ, or a negative `CRYPTO_ERR_*` code if either
+ * key fails to parse.
+ */
+int32_t keys_match(const uint8_t *priv_key_ptr,
+                   uintptr_t priv_key_len,
+                   const uint8_t *pub_key_ptr,
+                   uintptr_t pub_key_len);
+
+/**
+ * Compares two public keys by modulus and exponent, so formatting and
+ * encoding differences do not matter: each may be SPKI or PKCS#1 PEM (with
+ * any line length, CRLF or stray whitespace, as `canonicalize_public_key_pem`
+ * accepts), or SPKI DER. Returns 1 if they are the same key, 0 if not, or a negative
+ * `CRYPTO_ERR_*` code if either fails to parse.
+ */
+int32_t public_keys_equal(const uint8_t *a_ptr,
+                          uintptr_t a_len,
+                          const uint8_t *b_ptr,
+                          uintptr_t b_len);
+
+/**
+ * Signs an already-computed digest with PKCS#1 v1.5, skipping the internal
+ * hashing step. `digest_len` must equal the output size of `hash_alg`, as
+ * returned by `digest_payload`.
+ */
+bool sign_prehashed(const uint8_t *digest_ptr,
+                    uintptr_t digest_len,
+                    const uint8_t *priv_key_ptr,
+                    uintptr_t priv_key_len,
+                    uint8_t **out_sig_ptr,
+                    uintptr_t *out_sig_len,
+                    uintptr_t *out_sig_cap,
+                    uint32_t hash_alg);
+
+/**
+ * Verifies a PKCS#1 v1.5 signature over an already-computed digest.
+ * `digest_len` must equal the output size of `hash_alg`.
+ */
+bool verify_prehashed(const uint8_t *digest_ptr,
+                      uintptr_t digest_len,
+                      const uint8_t *sig_ptr,
+                      uintptr_t sig_len,
+                      const uint8_t *pub_key_ptr,
+                      uintptr_t pub_key_len,
+                      uint32_t hash_alg);
+
+/**
+ * Same as `verify_signature_ex` but also requires the payload's `hash_alg`
+ * digest to equal `expected_digest`, compared in constant time. The payload
+ * is hashed once and that digest is what the signature is checked against.
+ * A digest that differs returns `CRYPTO_ERR_DIGEST_MISMATCH` without checking
+ * the signature; an `expected_digest_len` other than the hash output size
+ * returns `CRYPTO_ERR_INVALID_LENGTH`.
+ */
+int32_t verify_signature_expect_digest(const uint8_t *payload_ptr,
+                                       uintptr_t payload_len,
+                                       const uint8_t *sig_ptr,
+                                       uintptr_t sig_len,
+                                       const uint8_t *pub_key_ptr,
+                                       uintptr_t pub_key_len,
+                                       const uint8_t *expected_digest_ptr,
+                                       uintptr_t expected_digest_len,
+                                       uint32_t hash_alg);
+
+/**
+ * Writes the 32-byte SHA-256 digest of `payload` into `out`, which must have
+ * room for at least 32 bytes.
+ */
+bool sha256_digest(const uint8_t *payload_ptr, uintptr_t payload_len, uint8_t *out);
+
+/**
+ * Writes the digest of `payload` under `hash_alg` into `out` and returns its
+ * length, or 0 on failure. Hash once with this and pass the result to
+ * `sign_prehashed` for each key when signing one payload with several keys.
+ * Digest lengths: SHA-1 20, SHA-256 and SHA3-256 32, SHA-384 and SHA3-384 48,
+ * SHA-512 and SHA3-512 64 bytes. `out` must have room for that many bytes;
+ * `MAX_DIGEST_LEN` always suffices.
+ */
+uintptr_t digest_payload(const uint8_t *payload_ptr,
+                         uintptr_t payload_len,
+                         uint32_t hash_alg,
+                         uint8_t *out);
+
+/**
+ * Writes the digest of `payload` under `hash_alg` into `out`. Fails without
+ * writing anything if `out_cap` is smaller than the digest length.
+ */
+bool hash_digest(const uint8_t *payload_ptr,
+                 uintptr_t payload_len,
+                 uint8_t *out,
+                 uintptr_t out_cap,
+                 uint32_t hash_alg);
+
+/**
+ * Starts a streaming SHA-256 context. Release it with `hash_ctx_free`.
+ */
+struct HashCtx *hash_ctx_new(void);
+
+bool hash_ctx_update(struct HashCtx *ctx, const uint8_t *data_ptr, uintptr_t data_len);
+
+/**
+ * Signs everything fed into `ctx` with PKCS#1 v1.5 SHA-256. The context is
+ * reset afterwards and can be reused, but must still be freed. A key that
+ * fails to parse leaves the context untouched, so the call can be retried.
+ */
+bool sign_ctx_finalize(struct HashCtx *ctx,
+                       const uint8_t *priv_key_ptr,
+                       uintptr_t priv_key_len,
+                       uint8_t **out_sig_ptr,
+                       uintptr_t *out_sig_len,
+                       uintptr_t *out_sig_cap);
+
+/**
+ * Verifies a PKCS#1 v1.5 SHA-256 signature over everything fed into `ctx`.
+ * The context is reset afterwards and can be reused, but must still be freed.
+ * A key that fails to parse leaves the context untouched, as in
+ * `sign_ctx_finalize`.
+ */
+bool verify_ctx_finalize(struct HashCtx *ctx,
+                         const uint8_t *sig_ptr,
+                         uintptr_t sig_len,
+                         const uint8_t *pub_key_ptr,
+                         uintptr_t pub_key_len);
+
+void hash_ctx_free(struct HashCtx *ctx);
+
+/**
+ * Same as `verify_signature` but takes the PEM public key as a NUL-terminated
+ * C string.
+ */
+bool verify_signature_cstr(const uint8_t *payload_ptr,
+                           uintptr_t payload_len,
+                           const uint8_t *sig_ptr,
+                           uintptr_t sig_len,
+                           const char *pub_key,
+                           uint32_t hash_alg);
+
+/**
+ * Same as `sign_payload` but takes the PEM private key as a NUL-terminated
+ * C string.
+ */
+bool sign_payload_cstr(const uint8_t *payload_ptr,
+                       uintptr_t payload_len,
+                       const char *priv_key,
+                       uint8_t **out_sig_ptr,
+                       uintptr_t *out_sig_len,
+                       uintptr_t *out_sig_cap,
+                       uint32_t hash_alg);
+
+/**
+ * Verifies `sig` against each key in turn and returns the index of the first
+ * key that accepts it, or -1 if none do. Keys that fail to parse are skipped.
+ */
+int32_t verify_signature_multi(const uint8_t *payload_ptr,
+                               uintptr_t payload_len,
+                               const uint8_t *sig_ptr,
+                               uintptr_t sig_len,
+                               const KeyEntry *keys_ptr,
+                               uintptr_t keys_len,
+                               uint32_t hash_alg);
+
+/**
+ * Same as `sign_payload` but frames the signature with the signer's key id
+ * so a verifier can pick the key directly. The blob is exactly
+ * `kid || signature`: a 4-byte kid (`KID_LEN`), the first 4 bytes of
+ * `public_key_fingerprint` (SHA-256 of the SPKI DER public key), followed by
+ * the modulus-sized PKCS#1 v1.5 signature. The kid is not signed.
+ */
+bool sign_payload_with_kid(const uint8_t *payload_ptr,
+                           uintptr_t payload_len,
+                           const uint8_t *priv_key_ptr,
+                           uintptr_t priv_key_len,
+                           uint8_t **out_ptr,
+                           uintptr_t *out_len,
+                           uintptr_t *out_cap,
+                           uint32_t hash_alg);
+
+/**
+ * Verifies a `sign_payload_with_kid` blob against `keys_len` candidate PEM
+ * public keys. Only keys whose kid matches the blob's are tried (all of them
+ * if several collide), so one RSA verification is usual however many keys are
+ * configured. Keys that fail to parse are skipped. Returns
+ * `CRYPTO_ERR_SIGNATURE_MISMATCH` if no matching key verifies and
+ * `CRYPTO_ERR_MALFORMED_TOKEN` for a blob shorter than the kid.
+ */
+int32_t verify_payload_with_kid(const uint8_t *payload_ptr,
+                                uintptr_t payload_len,
+                                const uint8_t *blob_ptr,
+                                uintptr_t blob_len,
+                                const KeyEntry *keys,
+                                uintptr_t keys_len,
+                                uint32_t hash_alg);
+
+/**
+ * Wraps an existing signature in the crate's versioned, self-describing
+ * container:
+ *
+ * `magic "RCSG" (4) || version 0x01 (1) || alg (1) || kid_len (1) || kid || sig_len (2, big-endian) || sig`
+ *
+ * `alg` is one of `SEALED_ALG_*`. `kid` is optional (null/0 for none), at
+ * most 255 bytes, and opaque to this library; `sig` must be 1..=65535 bytes.
+ * Neither the header nor the kid is signed. Released with `free_signature`.
+ */
+bool seal_signature(uint32_t alg,
+                    const uint8_t *kid_ptr,
+                    uintptr_t kid_len,
+                    const uint8_t *sig_ptr,
+                    uintptr_t sig_len,
+                    uint8_t **out_ptr,
+                    uintptr_t *out_len,
+                    uintptr_t *out_cap);
+
+/**
+ * Parses a `seal_signature` container without verifying it. On `CRYPTO_OK`
+ * writes the `SEALED_ALG_*` value to `out_alg` and points `out_kid_ptr` /
+ * `out_sig_ptr` into `container` itself (no copy; valid as long as the
+ * container is), with an empty kid reported as length 0. Truncated input, a
+ * wrong magic, an unknown version, an empty signature or trailing bytes
+ * return `CRYPTO_ERR_MALFORMED_TOKEN`; an unknown alg returns
+ * `CRYPTO_ERR_UNSUPPORTED_ALG`.
+ */
+int32_t open_signature(const uint8_t *container_ptr,
+                       uintptr_t container_len,
+                       uint32_t *out_alg,
+                       const uint8_t **out_kid_ptr,
+                       uintptr_t *out_kid_len,
+                       const uint8_t **out_sig_ptr,
+                       uintptr_t *out_sig_len);
+
+/**
+ * Opens a `seal_signature` container and verifies its signature over the
+ * payload with the scheme its alg byte names. `pub_key` is an RSA PEM public
+ * key for the RS and PS algs and an Ed25519 SPKI PEM for EdDSA. The kid is not
+ * checked: callers that route by kid read it with `open_signature` first.
+ * Container errors are as for `open_signature`; otherwise codes match
+ * `verify_signature_ex`.
+ */
+int32_t verify_sealed(const uint8_t *payload_ptr,
+                      uintptr_t payload_len,
+                      const uint8_t *container_ptr,
+                      uintptr_t container_len,
+                      const uint8_t *pub_key_ptr,
+                      uintptr_t pub_key_len);
+
+/**
+ * Signs the payload with PKCS#1 v1.5 under both SHA-256 and SHA-512, for
+ * migrations where old and new verifiers must both accept the result. The blob
+ * is exactly `sig_sha256 || sig_sha512`, SHA-256 first, each half the modulus
+ * size (`2 * modulus_bytes` in total) with no header or length prefixes. Each
+ * half is an ordinary `sign_payload` signature for its hash.
+ */
+bool sign_payload_dual(const uint8_t *payload_ptr,
+                       uintptr_t payload_len,
+                       const uint8_t *priv_key_ptr,
+                       uintptr_t priv_key_len,
+                       uint8_t **out_ptr,
+                       uintptr_t *out_len,
+                       uintptr_t *out_cap);
+
+/**
+ * Verifies a `sign_payload_dual` blob. With `require_both` set both the
+ * SHA-256 and SHA-512 halves must verify; otherwise either one is enough.
+ * Returns `CRYPTO_ERR_INVALID_LENGTH` if the blob is not twice the modulus
+ * size and `CRYPTO_ERR_SIGNATURE_MISMATCH` if the requirement is not met.
+ */
+int32_t verify_payload_dual(const uint8_t *payload_ptr,
+                            uintptr_t payload_len,
+                            const uint8_t *blob_ptr,
+                            uintptr_t blob_len,
+                            const uint8_t *pub_key_ptr,
+                            uintptr_t pub_key_len,
+                            bool require_both);
+
+/**
+ * Signs the payload with PKCS#1 v1.5 over SHA-256 and returns one buffer
+ * holding `[32-byte SHA-256 digest][signature]`, so an audit record can be
+ * re-verified later with `verify_digest_signature` without the payload. The
+ * signature is an ordinary `sign_payload` SHA-256 signature. Released with
+ * `free_signature`.
+ */
+bool sign_payload_with_digest(const uint8_t *payload_ptr,
+                              uintptr_t payload_len,
+                              const uint8_t *priv_key_ptr,
+                              uintptr_t priv_key_len,
+                              uint8_t **out_ptr,
+                              uintptr_t *out_len,
+                              uintptr_t *out_cap);
+
+/**
+ * Verifies a `sign_payload_with_digest` record: the first 32 bytes are taken
+ * as the SHA-256 pre-hash and the rest must be a signature over it. The
+ * digest is not authenticated by anything beyond that signature, and
+ * `CRYPTO_OK` says nothing about which payload it came from; to tie it to a
+ * payload, use `verify_signature_expect_digest`. A record that is not 32
+ * bytes plus the modulus size returns `CRYPTO_ERR_INVALID_LENGTH`.
+ */
+int32_t verify_digest_signature(const uint8_t *digest_and_sig_ptr,
+                                uintptr_t total_len,
+                                const uint8_t *pub_key_ptr,
+                                uintptr_t pub_key_len);
+
+/**
+ * Signs with PKCS#1 v1.5 like `sign_payload` and appends an HMAC-SHA256 tag
+ * under `mac_key`, so receivers can drop corrupted frames before an RSA
+ * verify. The blob is `sig_len (2 bytes, big-endian) || sig || tag`, with the
+ * 32-byte tag computed over `payload || sig_len || sig`. Released with
+ * `free_signature`.
+ */
+bool sign_payload_with_mac(const uint8_t *payload_ptr,
+                           uintptr_t payload_len,
+                           const uint8_t *priv_key_ptr,
+                           uintptr_t priv_key_len,
+                           const uint8_t *mac_key_ptr,
+                           uintptr_t mac_key_len,
+                           uint8_t **out_ptr,
+                           uintptr_t *out_len,
+                           uintptr_t *out_cap,
+                           uint32_t hash_alg);
+
+/**
+ * Verifies a `sign_payload_with_mac` blob, checking the HMAC tag (constant
+ * time) before the RSA signature. Returns `CRYPTO_ERR_MALFORMED_TOKEN` if the
+ * length prefix does not match the blob, `CRYPTO_ERR_TAG_MISMATCH` if the tag
+ * is wrong (the signature is then not checked) and
+ * `CRYPTO_ERR_SIGNATURE_MISMATCH` if only the signature is.
+ */
+int32_t verify_payload_with_mac(const uint8_t *payload_ptr,
+                                uintptr_t payload_len,
+                                const uint8_t *blob_ptr,
+                                uintptr_t blob_len,
+                                const uint8_t *pub_key_ptr,
+                                uintptr_t pub_key_len,
+                                const uint8_t *mac_key_ptr,
+                                uintptr_t mac_key_len,
+                                uint32_t hash_alg);
+
+/**
+ * Verifies that at least `threshold` distinct keys out of `key_count` PEM
+ * public keys each produced one of the `sig_count` signatures over the
+ * payload. Each signature and each key counts at most once, and duplicate
+ * keys count once. The number of valid distinct signers is written to
+ * `out_valid_count` (may be null) on both `CRYPTO_OK` and
+ * `CRYPTO_ERR_THRESHOLD_NOT_MET`. A key that fails to parse fails the call.
+ */
+int32_t verify_multi_sig(const uint8_t *payload_ptr,
+                         uintptr_t payload_len,
+                         const struct Slice *sigs,
+                         uintptr_t sig_count,
+                         const struct Slice *keys,
+                         uintptr_t key_count,
+                         uintptr_t threshold,
+                         uint32_t hash_alg,
+                         uintptr_t *out_valid_count);
+
+/**
+ * Same as `sign_payload` but signs the concatenation of `segment_count`
+ * segments, hashed in order, without the caller joining them first.
+ */
+bool sign_payload_iov(const IoSlice *segments,
+                      uintptr_t segment_count,
+                      const uint8_t *priv_key_ptr,
+                      uintptr_t priv_key_len,
+                      uint8_t **out_sig_ptr,
+                      uintptr_t *out_sig_len,
+                      uintptr_t *out_sig_cap,
+                      uint32_t hash_alg);
+
+/**
+ * Verification counterpart of `sign_payload_iov`.
+ */
+bool verify_signature_iov(const IoSlice *segments,
+                          uintptr_t segment_count,
+                          const uint8_t *sig_ptr,
+                          uintptr_t sig_len,
+                          const uint8_t *pub_key_ptr,
+                          uintptr_t pub_key_len,
+                          uint32_t hash_alg);
+
+/**
+ * Verifies `count` independent (payload, signature) pairs against one PEM
+ * public key in parallel, writing each outcome to `out_results[i]`.
+ *
+ * RSA public-key verification is stateless and `RsaPublicKey` is immutable
+ * once parsed, so sharing the single parsed key across rayon workers is sound.
+ */
+int32_t verify_batch(const struct Slice *payloads,
+                     const struct Slice *sigs,
+                     uintptr_t count,
+                     const uint8_t *pub_key_ptr,
+                     uintptr_t pub_key_len,
+                     bool *out_results,
+                     uint32_t hash_alg);
+
+/**
+ * Verifies `entries_len` (signature, PEM public key) pairs over one payload,
+ * which is hashed once and shared by every entry. `out_results[i]` is set to
+ * whether entry `i` verified; an entry with a null slice or a key that fails
+ * to parse is `false` rather than failing the call. Returns the number of
+ * valid entries, or a negative `CRYPTO_*` code if the call itself is bad.
+ */
+int32_t verify_attestation(const uint8_t *payload_ptr,
+                           uintptr_t payload_len,
+                           const struct SigKeyPair *entries,
+                           uintptr_t entries_len,
+                           bool *out_results,
+                           uint32_t hash_alg);
+
+/**
+ * Attributes each of `count` (payload, signature) pairs to a key: writes to
+ * `out_key_index[i]` the index of the first of the `key_count` PEM public keys
+ * that verifies pair `i`, or -1 if none does. Keys are parsed once up front
+ * and keys that fail to parse never match. Each payload is hashed once and
+ * pairs are checked in parallel as in `verify_batch`.
+ */
+int32_t match_signatures(const struct Slice *payloads,
+                         const struct Slice *sigs,
+                         uintptr_t count,
+                         const KeyEntry *keys,
+                         uintptr_t key_count,
+                         intptr_t *out_key_index,
+                         uint32_t hash_alg);
+
+/**
+ * Sparse counterpart of `match_signatures` for large attribution jobs: checks
+ * each of `count` (payload, signature) pairs against every one of the
+ * `key_count` PEM public keys and keeps only the matches, in pair order and
+ * then key order. Unlike `match_signatures`, a pair that several keys verify
+ * is reported once per key. The matching itself is done here, in parallel;
+ * `match_results_next` only walks the result. Returns null on error, with the
+ * reason available from the last-error functions.
+ */
+struct MatchResults *match_signatures_begin(const struct Slice *payloads,
+                                            const struct Slice *sigs,
+                                            uintptr_t count,
+                                            const KeyEntry *keys,
+                                            uintptr_t key_count,
+                                            uint32_t hash_alg);
+
+/**
+ * Writes the next match from `match_signatures_begin` to `out_payload_idx`
+ * (the pair's index) and `out_key_idx` (the key's index) and returns true,
+ * or returns false once every match has been read.
+ */
+bool match_results_next(struct MatchResults *handle,
+                        uintptr_t *out_payload_idx,
+                        intptr_t *out_key_idx);
+
+void match_results_free(struct MatchResults *handle);
+
+/**
+ * Signs `payload` with a PKCS#8 PEM Ed25519 private key. The message is
+ * signed directly, without the SHA-256 step the RSA paths apply. The 64-byte
+ * signature is released with `free_signature`.
+ */
+bool ed25519_sign(const uint8_t *payload_ptr,
                   uintptr_t payload_len,
                   const uint8_t *priv_key_ptr,
                   uintptr_t priv_key_len,
@@ -18,4 +2401,242 @@ bool sign_payload(const uint8_t *payload_ptr,
                   uintptr_t *out_sig_len,
                   uintptr_t *out_sig_cap);
 
-void free_signature(uint8_t *sig_ptr, uintptr_t sig_len, uintptr_t sig_cap);
+/**
+ * Same as `ed25519_sign` but takes the private key as PKCS#8 DER.
+ */
+bool ed25519_sign_der(const uint8_t *payload_ptr,
+                      uintptr_t payload_len,
+                      const uint8_t *priv_key_ptr,
+                      uintptr_t priv_key_len,
+                      uint8_t **out_sig_ptr,
+                      uintptr_t *out_sig_len,
+                      uintptr_t *out_sig_cap);
+
+/**
+ * Verifies an Ed25519 signature with an SPKI PEM public key. Signatures that
+ * are not exactly 64 bytes are rejected.
+ */
+bool ed25519_verify(const uint8_t *payload_ptr,
+                    uintptr_t payload_len,
+                    const uint8_t *sig_ptr,
+                    uintptr_t sig_len,
+                    const uint8_t *pub_key_ptr,
+                    uintptr_t pub_key_len);
+
+/**
+ * Same as `ed25519_verify` but takes the public key as SPKI DER.
+ */
+bool ed25519_verify_der(const uint8_t *payload_ptr,
+                        uintptr_t payload_len,
+                        const uint8_t *sig_ptr,
+                        uintptr_t sig_len,
+                        const uint8_t *pub_key_ptr,
+                        uintptr_t pub_key_len);
+
+/**
+ * Parses `json`, re-serializes it in RFC 8785 canonical form (sorted keys, no
+ * insignificant whitespace) and signs that byte stream with a PEM private
+ * key. Input that is not valid JSON returns `CRYPTO_ERR_INVALID_JSON`. The
+ * signature is released with `free_signature`.
+ */
+int32_t sign_json_canonical(const uint8_t *json_ptr,
+                            uintptr_t json_len,
+                            const uint8_t *priv_key_ptr,
+                            uintptr_t priv_key_len,
+                            uint8_t **out_sig_ptr,
+                            uintptr_t *out_sig_len,
+                            uintptr_t *out_sig_cap,
+                            uint32_t hash_alg);
+
+/**
+ * Verifies a signature from `sign_json_canonical` over any serialization of
+ * the same JSON value.
+ */
+int32_t verify_json_canonical(const uint8_t *json_ptr,
+                              uintptr_t json_len,
+                              const uint8_t *sig_ptr,
+                              uintptr_t sig_len,
+                              const uint8_t *pub_key_ptr,
+                              uintptr_t pub_key_len,
+                              uint32_t hash_alg);
+
+/**
+ * Verifies an RSA COSE_Sign1 message (RS256/RS384/RS512 per its protected
+ * header) against a PEM public key. For a detached payload pass it in
+ * `external_payload_ptr`; otherwise pass null and the embedded payload is
+ * used. Malformed CBOR returns `CRYPTO_ERR_MALFORMED_TOKEN` and other
+ * algorithms `CRYPTO_ERR_UNSUPPORTED_ALG`.
+ */
+int32_t verify_cose_sign1(const uint8_t *message_ptr,
+                          uintptr_t message_len,
+                          const uint8_t *external_payload_ptr,
+                          uintptr_t external_payload_len,
+                          const uint8_t *pub_key_ptr,
+                          uintptr_t pub_key_len);
+
+/**
+ * Assembles and signs a compact RS256 JWT from serialized header and claims
+ * JSON, returning the ASCII token `header.claims.signature` in a buffer
+ * released with `free_signature`. The JSON is signed as given.
+ */
+bool jwt_sign_rs256(const uint8_t *header_json_ptr,
+                    uintptr_t header_json_len,
+                    const uint8_t *claims_json_ptr,
+                    uintptr_t claims_json_len,
+                    const uint8_t *priv_key_ptr,
+                    uintptr_t priv_key_len,
+                    uint8_t **out_ptr,
+                    uintptr_t *out_len,
+                    uintptr_t *out_cap);
+
+/**
+ * Verifies the RS256 signature of a compact JWT against a PEM public key.
+ * Returns `CRYPTO_ERR_MALFORMED_TOKEN` for a token without exactly three
+ * segments or with an empty or non-base64url signature. Header and claims are
+ * not inspected.
+ */
+int32_t jwt_verify_rs256(const uint8_t *token_ptr,
+                         uintptr_t token_len,
+                         const uint8_t *pub_key_ptr,
+                         uintptr_t pub_key_len);
+
+/**
+ * Signs `payload` with ECDSA P-256 / SHA-256 using a PKCS#8 or SEC1 PEM
+ * private key. Nonces are RFC 6979 deterministic, not random; see
+ * `ecdsa_p256_sign_deterministic`. A raw signature is always 64 bytes. The
+ * signature is released with `free_signature`.
+ */
+bool ecdsa_p256_sign(const uint8_t *payload_ptr,
+                     uintptr_t payload_len,
+                     const uint8_t *priv_key_ptr,
+                     uintptr_t priv_key_len,
+                     uint8_t **out_sig_ptr,
+                     uintptr_t *out_sig_len,
+                     uintptr_t *out_sig_cap,
+                     uint32_t sig_format);
+
+/**
+ * Same as `ecdsa_p256_sign`, named for callers that depend on reproducible
+ * output. Nonces are derived per RFC 6979 from the key and the SHA-256 of the
+ * payload, so the same key and payload always give byte-for-byte the same
+ * signature in either format.
+ */
+bool ecdsa_p256_sign_deterministic(const uint8_t *payload_ptr,
+                                   uintptr_t payload_len,
+                                   const uint8_t *priv_key_ptr,
+                                   uintptr_t priv_key_len,
+                                   uint8_t **out_sig_ptr,
+                                   uintptr_t *out_sig_len,
+                                   uintptr_t *out_sig_cap,
+                                   uint32_t sig_format);
+
+/**
+ * Verifies an ECDSA P-256 / SHA-256 signature with an SPKI PEM public key.
+ * With `ECDSA_SIG_RAW`, signatures that are not exactly 64 bytes are rejected.
+ */
+bool ecdsa_p256_verify(const uint8_t *payload_ptr,
+                       uintptr_t payload_len,
+                       const uint8_t *sig_ptr,
+                       uintptr_t sig_len,
+                       const uint8_t *pub_key_ptr,
+                       uintptr_t pub_key_len,
+                       uint32_t sig_format);
+
+/**
+ * Curve-generic `ecdsa_p256_sign`: signs with a PKCS#8 or SEC1 PEM private
+ * key on `curve`. `hash_alg` must be the JOSE hash for the curve (SHA-256 for
+ * P-256, SHA-384 for P-384, SHA-512 for P-521); any other combination, or a
+ * key on a different curve, returns false. Raw signatures are 64, 96 or 132
+ * bytes. P-521 signatures use random rather than RFC 6979 nonces; see
+ * `api::ecdsa_sign`.
+ */
+bool ecdsa_sign(uint32_t curve,
+                const uint8_t *payload_ptr,
+                uintptr_t payload_len,
+                const uint8_t *priv_key_ptr,
+                uintptr_t priv_key_len,
+                uint8_t **out_sig_ptr,
+                uintptr_t *out_sig_len,
+                uintptr_t *out_sig_cap,
+                uint32_t sig_format,
+                uint32_t hash_alg);
+
+/**
+ * Curve-generic `ecdsa_p256_verify` with an SPKI PEM public key on `curve`.
+ * The curve, hash and key must match as for `ecdsa_sign`. With
+ * `ECDSA_SIG_RAW`, signatures that are not exactly the curve's raw width are
+ * rejected.
+ */
+bool ecdsa_verify(uint32_t curve,
+                  const uint8_t *payload_ptr,
+                  uintptr_t payload_len,
+                  const uint8_t *sig_ptr,
+                  uintptr_t sig_len,
+                  const uint8_t *pub_key_ptr,
+                  uintptr_t pub_key_len,
+                  uint32_t sig_format,
+                  uint32_t hash_alg);
+
+/**
+ * Converts a DER P-256 ECDSA signature to the 64-byte `r || s` form, writing
+ * exactly 64 bytes to `out`. Malformed DER or out-of-range integers return
+ * false.
+ */
+bool ecdsa_sig_der_to_raw(const uint8_t *der_ptr, uintptr_t der_len, uint8_t *out);
+
+/**
+ * Converts a 64-byte `r || s` P-256 ECDSA signature to DER, released with
+ * `free_signature`. Any other length, or an out-of-range integer, returns
+ * false.
+ */
+bool ecdsa_sig_raw_to_der(const uint8_t *raw_ptr,
+                          uintptr_t raw_len,
+                          uint8_t **out_ptr,
+                          uintptr_t *out_len,
+                          uintptr_t *out_cap);
+
+/**
+ * Verifies a secp256k1 ECDSA signature over a precomputed digest.
+ * `digest_ptr` points to 32 bytes and `sig_ptr` to a 64-byte `r || s`
+ * signature; the public key is SEC1, compressed (33 bytes) or uncompressed
+ * (65 bytes). A malformed key returns `CRYPTO_ERR_KEY_PARSE`, an out-of-range
+ * `r` or `s` returns `CRYPTO_ERR_MALFORMED_TOKEN`, and a well-formed signature
+ * that does not verify (including high-S) returns
+ * `CRYPTO_ERR_SIGNATURE_MISMATCH`.
+ */
+int32_t secp256k1_verify(const uint8_t *digest_ptr,
+                         const uint8_t *sig_ptr,
+                         const uint8_t *pub_key_ptr,
+                         uintptr_t pub_key_len);
+
+/**
+ * Releases a buffer returned through a `(ptr, len, cap)` out-param triple.
+ * `sig_len` and `sig_cap` must be exactly the values that were returned with
+ * `sig_ptr`. Freeing is idempotent: the library tracks every live buffer, and
+ * a pointer that is not currently live (already freed, never returned, or
+ * null) or whose `sig_cap` does not match is ignored. Returns true only if
+ * this call released the buffer. Empty buffers own no memory, so releasing
+ * one always returns false. A call with `sig_len > sig_cap` cannot be a valid
+ * triple and is ignored (leaking the buffer) rather than risking a bad
+ * deallocation.
+ */
+bool free_signature(uint8_t *sig_ptr, uintptr_t sig_len, uintptr_t sig_cap);
+
+/**
+ * `free_signature` for buffers returned by the `_u64` functions. A length or
+ * capacity that does not fit in `size_t` cannot be a live buffer and is
+ * ignored.
+ */
+bool free_signature_u64(uint8_t *sig_ptr, uint64_t sig_len, uint64_t sig_cap);
+
+/**
+ * Zeroes `len` bytes at `ptr` in a caller-owned buffer, such as private key
+ * PEM after signing. Input buffers are left as they were handed over, with
+ * these exceptions, which wipe their secret input before returning:
+ * `sign_payload_encrypted` (the passphrase), `sign_payload_pkcs11` (the PIN),
+ * `encrypt_private_key_pkcs8` (the PEM and the passphrase),
+ * `decrypt_private_key_pkcs8` (the passphrase), and `argon2_hash` and
+ * `argon2_verify` (the password). The writes are volatile, so the compiler
+ * cannot drop them as dead stores. A null `ptr` is ignored.
+ */
+void secure_zero(uint8_t *ptr, uintptr_t len);
@@ -0,0 +1,3785 @@
+//! Safe Rust API over the same primitives the `extern "C"` surface exposes.
+//!
+//! The FFI functions in the crate root are thin wrappers around this module:
+//! they turn pointers into slices, call in here and map `CryptoError` to the
+//! `CRYPTO_*` status codes.
+
+use aes_gcm::aead::stream::{DecryptorBE32, EncryptorBE32};
+use aes_gcm::aead::{Aead, KeyInit, Payload};
+use aes_gcm::{Aes256Gcm, Nonce};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use coset::{iana, Algorithm, CborSerializable, CoseSign1, TaggedCborSerializable};
+use ed25519_dalek::{Signer as _, SigningKey, Verifier as _, VerifyingKey};
+use hmac::{Hmac, Mac};
+use k256::ecdsa::signature::hazmat::PrehashVerifier;
+use num_bigint_dig::ModInverse;
+use rsa::pkcs1::DecodeRsaPrivateKey;
+use rsa::pkcs8::{
+    DecodePrivateKey, DecodePublicKey, EncodePrivateKey, EncodePublicKey, LineEnding,
+};
+use rsa::rand_core::CryptoRngCore;
+use rsa::traits::PublicKeyParts;
+use rsa::{BigUint, Oaep, Pkcs1v15Sign, Pss, RsaPrivateKey, RsaPublicKey};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha384, Sha512};
+use sha3::{Sha3_256, Sha3_384, Sha3_512};
+use std::fmt;
+use std::fs::File;
+use std::io::{self, Read};
+use std::path::Path;
+use std::str::Utf8Error;
+use std::sync::OnceLock;
+use subtle::{Choice, ConditionallySelectable, ConstantTimeEq};
+use x509_cert::crl::CertificateList;
+use x509_cert::der::asn1::{
+    Any, BmpString, Ia5StringRef, PrintableStringRef, TeletexStringRef, Utf8StringRef,
+};
+use x509_cert::der::oid::db::rfc4519::COMMON_NAME;
+use x509_cert::der::oid::db::rfc5912::{
+    SHA_1_WITH_RSA_ENCRYPTION, SHA_256_WITH_RSA_ENCRYPTION, SHA_384_WITH_RSA_ENCRYPTION,
+    SHA_512_WITH_RSA_ENCRYPTION,
+};
+use x509_cert::der::{Decode, Encode};
+use x509_cert::der::{Tag, Tagged};
+use x509_cert::ext::pkix::name::GeneralName;
+use x509_cert::ext::pkix::{BasicConstraints, SubjectAltName};
+use x509_cert::spki::AlgorithmIdentifierOwned;
+use x509_cert::Certificate;
+use zeroize::Zeroizing;
+
+use crate::rng::LibRng;
+use crate::{
+    CRYPTO_ERR_AEAD, CRYPTO_ERR_BAD_PASSPHRASE, CRYPTO_ERR_BUFFER_TOO_SMALL,
+    CRYPTO_ERR_CERT_EXPIRED, CRYPTO_ERR_CERT_NOT_CA, CRYPTO_ERR_CERT_REVOKED, CRYPTO_ERR_CRYPTO,
+    CRYPTO_ERR_DIGEST_MISMATCH, CRYPTO_ERR_INCONSISTENT_KEY, CRYPTO_ERR_INVALID_ARGUMENT,
+    CRYPTO_ERR_INVALID_JSON, CRYPTO_ERR_INVALID_JWK, CRYPTO_ERR_INVALID_LENGTH,
+    CRYPTO_ERR_INVALID_UTF8, CRYPTO_ERR_IO, CRYPTO_ERR_KEY_PARSE, CRYPTO_ERR_MALFORMED_TOKEN,
+    CRYPTO_ERR_NOT_SUPPORTED, CRYPTO_ERR_NULL_POINTER, CRYPTO_ERR_PASSWORD_HASH,
+    CRYPTO_ERR_PASSWORD_MISMATCH, CRYPTO_ERR_PAYLOAD_TOO_LARGE, CRYPTO_ERR_POLICY_VIOLATION,
+    CRYPTO_ERR_READ_CALLBACK, CRYPTO_ERR_SIGNATURE_MISMATCH, CRYPTO_ERR_TAG_MISMATCH,
+    CRYPTO_ERR_THRESHOLD_NOT_MET, CRYPTO_ERR_TIMESTAMP_SKEW, CRYPTO_ERR_TOKEN,
+    CRYPTO_ERR_UNSUPPORTED_ALG, CRYPTO_ERR_UNTRUSTED_ROOT, ECDSA_CURVE_P256, ECDSA_CURVE_P384,
+    ECDSA_CURVE_P521, ECDSA_SIG_DER, ECDSA_SIG_RAW, HASH_ALG_SHA1, HASH_ALG_SHA256,
+    HASH_ALG_SHA384, HASH_ALG_SHA3_256, HASH_ALG_SHA3_384, HASH_ALG_SHA3_512, HASH_ALG_SHA512,
+    RSA_PADDING_OAEP_SHA256, RSA_PADDING_PKCS1V15, SEALED_ALG_EDDSA, SEALED_ALG_PS256,
+    SEALED_ALG_RS256, SEALED_ALG_RS384, SEALED_ALG_RS512, SIG_SCHEME_ECDSA, SIG_SCHEME_ED25519,
+    SIG_SCHEME_RSA_PKCS1V15, SIG_SCHEME_RSA_PSS,
+};
+
+#[derive(Debug)]
+pub enum CryptoError {
+    NullPointer,
+    InvalidUtf8(Utf8Error),
+    KeyParse(String),
+    Crypto(rsa::Error),
+    SignatureMismatch,
+    UnsupportedAlg(u32),
+    BufferTooSmall {
+        required: usize,
+        available: usize,
+    },
+    BadPassphrase,
+    TagMismatch,
+    InvalidLength {
+        what: &'static str,
+        expected: usize,
+        actual: usize,
+    },
+    MalformedToken(&'static str),
+    UnsupportedAlgId(String),
+    InvalidJson(serde_json::Error),
+    PasswordMismatch,
+    CertExpired,
+    CertNotCa,
+    UntrustedRoot,
+    Io(std::io::Error),
+    InvalidArgument(&'static str),
+    TimestampSkew {
+        timestamp: i64,
+        now: i64,
+        max_skew_secs: u64,
+    },
+    ThresholdNotMet {
+        valid: usize,
+        threshold: usize,
+    },
+    PayloadTooLarge {
+        len: usize,
+        max: usize,
+    },
+    CertRevoked,
+    InvalidJwk(&'static str),
+    NotSupported(&'static str),
+    Token(String),
+    PolicyViolation(String),
+    ReadCallback(isize),
+    DigestMismatch,
+    InconsistentKey(String),
+    Aead(&'static str),
+    PasswordHash(String),
+}
+
+impl CryptoError {
+    /// The `CRYPTO_*` status code the FFI layer reports for this error.
+    pub fn code(&self) -> i32 {
+        match self {
+            CryptoError::NullPointer => CRYPTO_ERR_NULL_POINTER,
+            CryptoError::InvalidUtf8(_) => CRYPTO_ERR_INVALID_UTF8,
+            CryptoError::KeyParse(_) => CRYPTO_ERR_KEY_PARSE,
+            CryptoError::Crypto(_) => CRYPTO_ERR_CRYPTO,
+            CryptoError::SignatureMismatch => CRYPTO_ERR_SIGNATURE_MISMATCH,
+            CryptoError::UnsupportedAlg(_) => CRYPTO_ERR_UNSUPPORTED_ALG,
+            CryptoError::BufferTooSmall { .. } => CRYPTO_ERR_BUFFER_TOO_SMALL,
+            CryptoError::BadPassphrase => CRYPTO_ERR_BAD_PASSPHRASE,
+            CryptoError::TagMismatch => CRYPTO_ERR_TAG_MISMATCH,
+            CryptoError::InvalidLength { .. } => CRYPTO_ERR_INVALID_LENGTH,
+            CryptoError::MalformedToken(_) => CRYPTO_ERR_MALFORMED_TOKEN,
+            CryptoError::UnsupportedAlgId(_) => CRYPTO_ERR_UNSUPPORTED_ALG,
+            CryptoError::InvalidJson(_) => CRYPTO_ERR_INVALID_JSON,
+            CryptoError::PasswordMismatch => CRYPTO_ERR_PASSWORD_MISMATCH,
+            CryptoError::CertExpired => CRYPTO_ERR_CERT_EXPIRED,
+            CryptoError::CertNotCa => CRYPTO_ERR_CERT_NOT_CA,
+            CryptoError::UntrustedRoot => CRYPTO_ERR_UNTRUSTED_ROOT,
+            CryptoError::Io(_) => CRYPTO_ERR_IO,
+            CryptoError::InvalidArgument(_) => CRYPTO_ERR_INVALID_ARGUMENT,
+            CryptoError::TimestampSkew { .. } => CRYPTO_ERR_TIMESTAMP_SKEW,
+            CryptoError::ThresholdNotMet { .. } => CRYPTO_ERR_THRESHOLD_NOT_MET,
+            CryptoError::PayloadTooLarge { .. } => CRYPTO_ERR_PAYLOAD_TOO_LARGE,
+            CryptoError::CertRevoked => CRYPTO_ERR_CERT_REVOKED,
+            CryptoError::InvalidJwk(_) => CRYPTO_ERR_INVALID_JWK,
+            CryptoError::NotSupported(_) => CRYPTO_ERR_NOT_SUPPORTED,
+            CryptoError::Token(_) => CRYPTO_ERR_TOKEN,
+            CryptoError::PolicyViolation(_) => CRYPTO_ERR_POLICY_VIOLATION,
+            CryptoError::ReadCallback(_) => CRYPTO_ERR_READ_CALLBACK,
+            CryptoError::DigestMismatch => CRYPTO_ERR_DIGEST_MISMATCH,
+            CryptoError::InconsistentKey(_) => CRYPTO_ERR_INCONSISTENT_KEY,
+            CryptoError::Aead(_) => CRYPTO_ERR_AEAD,
+            CryptoError::PasswordHash(_) => CRYPTO_ERR_PASSWORD_HASH,
+        }
+    }
+}
+
+impl fmt::Display for CryptoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CryptoError::NullPointer => write!(f, "null pointer argument"),
+            CryptoError::InvalidUtf8(e) => write!(f, "key PEM is not valid UTF-8: {e}"),
+            CryptoError::KeyParse(msg) => write!(f, "{msg}"),
+            CryptoError::Crypto(e) => write!(f, "RSA: {e}"),
+            CryptoError::SignatureMismatch => write!(f, "signature does not verify"),
+            CryptoError::UnsupportedAlg(alg) => write!(f, "unsupported hash_alg {alg}"),
+            CryptoError::BufferTooSmall {
+                required,
+                available,
+            } => {
+                write!(f, "output needs {required} bytes, buffer has {available}")
+            }
+            CryptoError::BadPassphrase => write!(f, "wrong passphrase for encrypted private key"),
+            CryptoError::TagMismatch => write!(f, "authentication tag does not match"),
+            CryptoError::InvalidLength {
+                what,
+                expected,
+                actual,
+            } => {
+                write!(f, "{what} must be {expected} bytes, got {actual}")
+            }
+            CryptoError::MalformedToken(why) => write!(f, "malformed token: {why}"),
+            CryptoError::UnsupportedAlgId(alg) => write!(f, "unsupported {alg}"),
+            CryptoError::InvalidJson(e) => write!(f, "invalid JSON: {e}"),
+            CryptoError::PasswordMismatch => write!(f, "password does not match stored hash"),
+            CryptoError::CertExpired => write!(f, "certificate is outside its validity period"),
+            CryptoError::CertNotCa => write!(f, "issuing certificate is not marked as a CA"),
+            CryptoError::UntrustedRoot => {
+                write!(f, "certificate chain does not lead to the trusted root")
+            }
+            CryptoError::Io(e) => write!(f, "I/O: {e}"),
+            CryptoError::InvalidArgument(why) => write!(f, "invalid argument: {why}"),
+            CryptoError::TimestampSkew {
+                timestamp,
+                now,
+                max_skew_secs,
+            } => {
+                write!(
+                    f,
+                    "timestamp {timestamp} is more than {max_skew_secs}s from current time {now}"
+                )
+            }
+            CryptoError::ThresholdNotMet { valid, threshold } => {
+                write!(f, "{valid} of required {threshold} signatures are valid")
+            }
+            CryptoError::PayloadTooLarge { len, max } => {
+                write!(f, "payload of {len} bytes exceeds the {max}-byte limit")
+            }
+            CryptoError::CertRevoked => write!(f, "certificate is listed in the CRL"),
+            CryptoError::InvalidJwk(why) => write!(f, "invalid JWK: {why}"),
+            CryptoError::NotSupported(what) => write!(f, "not supported in this build: {what}"),
+            CryptoError::Token(msg) => write!(f, "PKCS#11: {msg}"),
+            CryptoError::PolicyViolation(why) => write!(f, "key rejected by verify policy: {why}"),
+            CryptoError::ReadCallback(rc) => write!(f, "read callback failed (returned {rc})"),
+            CryptoError::DigestMismatch => {
+                write!(f, "payload digest does not match the expected digest")
+            }
+            CryptoError::InconsistentKey(why) => {
+                write!(f, "RSA key components are inconsistent: {why}")
+            }
+            CryptoError::Aead(why) => write!(f, "AES-GCM: {why}"),
+            CryptoError::PasswordHash(msg) => write!(f, "Argon2: {msg}"),
+        }
+    }
+}
+
+impl std::error::Error for CryptoError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            CryptoError::InvalidUtf8(e) => Some(e),
+            CryptoError::Crypto(e) => Some(e),
+            CryptoError::InvalidJson(e) => Some(e),
+            CryptoError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<Utf8Error> for CryptoError {
+    fn from(e: Utf8Error) -> Self {
+        CryptoError::InvalidUtf8(e)
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum HashAlg {
+    Sha256,
+    Sha384,
+    Sha512,
+    Sha1,
+    Sha3_256,
+    Sha3_384,
+    Sha3_512,
+}
+
+impl HashAlg {
+    pub(crate) fn from_u32(hash_alg: u32) -> Option<HashAlg> {
+        match hash_alg {
+            HASH_ALG_SHA256 => Some(HashAlg::Sha256),
+            HASH_ALG_SHA384 => Some(HashAlg::Sha384),
+            HASH_ALG_SHA512 => Some(HashAlg::Sha512),
+            HASH_ALG_SHA1 => Some(HashAlg::Sha1),
+            HASH_ALG_SHA3_256 => Some(HashAlg::Sha3_256),
+            HASH_ALG_SHA3_384 => Some(HashAlg::Sha3_384),
+            HASH_ALG_SHA3_512 => Some(HashAlg::Sha3_512),
+            _ => None,
+        }
+    }
+
+    pub fn output_len(self) -> usize {
+        match self {
+            HashAlg::Sha256 => Sha256::output_size(),
+            HashAlg::Sha384 => Sha384::output_size(),
+            HashAlg::Sha512 => Sha512::output_size(),
+            HashAlg::Sha1 => Sha1::output_size(),
+            HashAlg::Sha3_256 => Sha3_256::output_size(),
+            HashAlg::Sha3_384 => Sha3_384::output_size(),
+            HashAlg::Sha3_512 => Sha3_512::output_size(),
+        }
+    }
+
+    pub fn digest(self, payload: &[u8]) -> Vec<u8> {
+        match self {
+            HashAlg::Sha256 => Sha256::digest(payload).to_vec(),
+            HashAlg::Sha384 => Sha384::digest(payload).to_vec(),
+            HashAlg::Sha512 => Sha512::digest(payload).to_vec(),
+            HashAlg::Sha1 => Sha1::digest(payload).to_vec(),
+            HashAlg::Sha3_256 => Sha3_256::digest(payload).to_vec(),
+            HashAlg::Sha3_384 => Sha3_384::digest(payload).to_vec(),
+            HashAlg::Sha3_512 => Sha3_512::digest(payload).to_vec(),
+        }
+    }
+
+    /// Digest of the concatenation of `segments`, without concatenating them.
+    pub fn digest_segments<'a>(self, segments: impl IntoIterator<Item = &'a [u8]>) -> Vec<u8> {
+        fn run<'a, D: Digest>(segments: impl IntoIterator<Item = &'a [u8]>) -> Vec<u8> {
+            let mut hasher = D::new();
+            for segment in segments {
+                hasher.update(segment);
+            }
+            hasher.finalize().to_vec()
+        }
+
+        match self {
+            HashAlg::Sha256 => run::<Sha256>(segments),
+            HashAlg::Sha384 => run::<Sha384>(segments),
+            HashAlg::Sha512 => run::<Sha512>(segments),
+            HashAlg::Sha1 => run::<Sha1>(segments),
+            HashAlg::Sha3_256 => run::<Sha3_256>(segments),
+            HashAlg::Sha3_384 => run::<Sha3_384>(segments),
+            HashAlg::Sha3_512 => run::<Sha3_512>(segments),
+        }
+    }
+
+    /// Digest of everything `reader` yields, read in 64 KiB blocks so memory
+    /// use does not grow with the input.
+    pub fn digest_reader(self, reader: impl Read) -> io::Result<Vec<u8>> {
+        fn run<D: Digest>(mut reader: impl Read) -> io::Result<Vec<u8>> {
+            let mut hasher = D::new();
+            let mut buf = vec![0u8; 64 * 1024];
+            loop {
+                match reader.read(&mut buf) {
+                    Ok(0) => return Ok(hasher.finalize().to_vec()),
+                    Ok(n) => hasher.update(&buf[..n]),
+                    Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                    Err(e) => return Err(e),
+                }
+            }
+        }
+
+        match self {
+            HashAlg::Sha256 => run::<Sha256>(reader),
+            HashAlg::Sha384 => run::<Sha384>(reader),
+            HashAlg::Sha512 => run::<Sha512>(reader),
+            HashAlg::Sha1 => run::<Sha1>(reader),
+            HashAlg::Sha3_256 => run::<Sha3_256>(reader),
+            HashAlg::Sha3_384 => run::<Sha3_384>(reader),
+            HashAlg::Sha3_512 => run::<Sha3_512>(reader),
+        }
+    }
+
+    /// Writes the digest of `payload` into the first `output_len()` bytes of
+    /// `out` without allocating. `out` must be at least that long.
+    pub fn digest_into(self, payload: &[u8], out: &mut [u8]) {
+        let out = &mut out[..self.output_len()];
+        match self {
+            HashAlg::Sha256 => out.copy_from_slice(&Sha256::digest(payload)),
+            HashAlg::Sha384 => out.copy_from_slice(&Sha384::digest(payload)),
+            HashAlg::Sha512 => out.copy_from_slice(&Sha512::digest(payload)),
+            HashAlg::Sha1 => out.copy_from_slice(&Sha1::digest(payload)),
+            HashAlg::Sha3_256 => out.copy_from_slice(&Sha3_256::digest(payload)),
+            HashAlg::Sha3_384 => out.copy_from_slice(&Sha3_384::digest(payload)),
+            HashAlg::Sha3_512 => out.copy_from_slice(&Sha3_512::digest(payload)),
+        }
+    }
+
+    // The scheme carries the DigestInfo prefix, so its OID always matches the
+    // digest produced above.
+    pub(crate) fn pkcs1v15(self) -> Pkcs1v15Sign {
+        match self {
+            HashAlg::Sha256 => Pkcs1v15Sign::new::<Sha256>(),
+            HashAlg::Sha384 => Pkcs1v15Sign::new::<Sha384>(),
+            HashAlg::Sha512 => Pkcs1v15Sign::new::<Sha512>(),
+            HashAlg::Sha1 => Pkcs1v15Sign::new::<Sha1>(),
+            HashAlg::Sha3_256 => Pkcs1v15Sign::new::<Sha3_256>(),
+            HashAlg::Sha3_384 => Pkcs1v15Sign::new::<Sha3_384>(),
+            HashAlg::Sha3_512 => Pkcs1v15Sign::new::<Sha3_512>(),
+        }
+    }
+
+    // MGF1 uses the same hash as the message digest.
+    pub(crate) fn pss(self, salt_len: usize) -> Pss {
+        match self {
+            HashAlg::Sha256 => Pss::new_with_salt::<Sha256>(salt_len),
+            HashAlg::Sha384 => Pss::new_with_salt::<Sha384>(salt_len),
+            HashAlg::Sha512 => Pss::new_with_salt::<Sha512>(salt_len),
+            HashAlg::Sha1 => Pss::new_with_salt::<Sha1>(salt_len),
+            HashAlg::Sha3_256 => Pss::new_with_salt::<Sha3_256>(salt_len),
+            HashAlg::Sha3_384 => Pss::new_with_salt::<Sha3_384>(salt_len),
+            HashAlg::Sha3_512 => Pss::new_with_salt::<Sha3_512>(salt_len),
+        }
+    }
+}
+
+/// Largest RSA modulus, in bits, that the key parsers and key generation
+/// accept. Larger keys are rejected with `KeyParse` before any key math.
+pub const RSA_MAX_MODULUS_BITS: usize = 8192;
+
+// Every public key is built here rather than through `rsa`'s decoders, which
+// cap the modulus at `RsaPublicKey::MAX_SIZE` (4096 bits).
+fn rsa_public_key(n: BigUint, e: BigUint) -> Result<RsaPublicKey, rsa::Error> {
+    RsaPublicKey::new_with_max_size(n, e, RSA_MAX_MODULUS_BITS)
+}
+
+// `rsa` puts no upper bound on private keys at all.
+fn check_private_key_size(private_key: RsaPrivateKey) -> Result<RsaPrivateKey, CryptoError> {
+    let bits = private_key.n().bits();
+    if bits > RSA_MAX_MODULUS_BITS {
+        return Err(CryptoError::KeyParse(format!(
+            "RSA modulus is {bits} bits, maximum is {RSA_MAX_MODULUS_BITS}"
+        )));
+    }
+    Ok(private_key)
+}
+
+fn parse_pkcs1_public_key_der(der: &[u8]) -> Result<RsaPublicKey, CryptoError> {
+    let key = pkcs1::RsaPublicKey::from_der(der)
+        .map_err(|e| CryptoError::KeyParse(format!("PKCS#1 DER: {e}")))?;
+    let n = BigUint::from_bytes_be(key.modulus.as_bytes());
+    let e = BigUint::from_bytes_be(key.public_exponent.as_bytes());
+    rsa_public_key(n, e).map_err(|e| CryptoError::KeyParse(format!("PKCS#1 DER: {e}")))
+}
+
+/// Parses an SPKI (`BEGIN PUBLIC KEY`) or PKCS#1 (`BEGIN RSA PUBLIC KEY`) PEM.
+pub fn parse_public_key_pem(pem: &str) -> Result<RsaPublicKey, CryptoError> {
+    let (label, der) = pem_rfc7468::decode_vec(pem.as_bytes())
+        .map_err(|e| CryptoError::KeyParse(format!("public key PEM: {e}")))?;
+    match label {
+        "PUBLIC KEY" => parse_public_key_der(&der),
+        "RSA PUBLIC KEY" => parse_pkcs1_public_key_der(&der),
+        other => Err(CryptoError::KeyParse(format!(
+            "PEM: unexpected label {other:?} for a public key"
+        ))),
+    }
+}
+
+// Private key parsers only ever borrow the caller's bytes. That buffer (and any
+// PEM string the caller built it from) is caller-owned and cannot be wiped from
+// here; everything we derive from it is zeroized on drop. Passphrases are the
+// exception: `sign_payload_encrypted` wipes the caller's buffer itself, and
+// `encrypt_private_key_pkcs8` wipes the plaintext key buffer as well.
+
+/// Parses a PKCS#8 or PKCS#1 PEM private key.
+pub fn parse_private_key_pem(pem: &str) -> Result<RsaPrivateKey, CryptoError> {
+    // Try PKCS8 first, then PKCS1
+    let private_key = match RsaPrivateKey::from_pkcs8_pem(pem) {
+        Ok(k) => k,
+        Err(pkcs8_err) => RsaPrivateKey::from_pkcs1_pem(pem).map_err(|e| {
+            CryptoError::KeyParse(format!("PKCS#8 PEM: {pkcs8_err}; PKCS#1 PEM: {e}"))
+        })?,
+    };
+    check_private_key_size(private_key)
+}
+
+/// Decrypts and parses a passphrase-protected PKCS#8 PEM
+/// (`BEGIN ENCRYPTED PRIVATE KEY`) RSA private key.
+///
+/// A wrong passphrase is reported as `BadPassphrase`, a PEM that is not a
+/// well-formed encrypted PKCS#8 document (or holds a non-RSA key) as `KeyParse`.
+pub fn parse_encrypted_private_key_pem(
+    pem: &str,
+    passphrase: &[u8],
+) -> Result<RsaPrivateKey, CryptoError> {
+    // Done in steps rather than via `from_pkcs8_encrypted_pem`, which folds every
+    // failure into one error and cannot tell a bad passphrase from a bad file.
+    let (label, doc) = pkcs8::Document::from_pem(pem)
+        .map_err(|e| CryptoError::KeyParse(format!("encrypted PKCS#8 PEM: {e}")))?;
+    if label != "ENCRYPTED PRIVATE KEY" {
+        return Err(CryptoError::KeyParse(format!(
+            "encrypted PKCS#8 PEM: unexpected label {label}"
+        )));
+    }
+
+    let info = pkcs8::EncryptedPrivateKeyInfo::try_from(doc.as_bytes())
+        .map_err(|e| CryptoError::KeyParse(format!("encrypted PKCS#8 PEM: {e}")))?;
+    let decrypted = info
+        .decrypt(passphrase)
+        .map_err(|_| CryptoError::BadPassphrase)?;
+
+    // A wrong passphrase occasionally still yields valid CBC padding; the
+    // plaintext is then garbage rather than a PrivateKeyInfo.
+    if pkcs8::PrivateKeyInfo::try_from(decrypted.as_bytes()).is_err() {
+        return Err(CryptoError::BadPassphrase);
+    }
+
+    RsaPrivateKey::from_pkcs8_der(decrypted.as_bytes())
+        .map_err(|e| CryptoError::KeyParse(format!("encrypted PKCS#8 PEM: {e}")))
+        .and_then(check_private_key_size)
+}
+
+/// Re-wraps a PKCS#8 or PKCS#1 PEM private key as encrypted PKCS#8 PEM using
+/// the `pkcs8` crate's defaults: PBES2 with scrypt and AES-256-CBC, and a fresh
+/// salt and IV per call.
+pub fn encrypt_private_key_pem(pem: &str, passphrase: &[u8]) -> Result<String, CryptoError> {
+    let encrypted = parse_private_key_pem(pem)?
+        .to_pkcs8_encrypted_pem(&mut LibRng, passphrase, LineEnding::LF)
+        .map_err(|e| CryptoError::KeyParse(format!("encrypted PKCS#8 PEM encode: {e}")))?;
+    Ok(encrypted.to_string())
+}
+
+/// Inverse of `encrypt_private_key_pem`: decrypts an encrypted PKCS#8 PEM and
+/// returns the key as plain PKCS#8 PEM. Errors match
+/// `parse_encrypted_private_key_pem`.
+pub fn decrypt_private_key_pem(
+    pem: &str,
+    passphrase: &[u8],
+) -> Result<Zeroizing<String>, CryptoError> {
+    parse_encrypted_private_key_pem(pem, passphrase)?
+        .to_pkcs8_pem(LineEnding::LF)
+        .map_err(|e| CryptoError::KeyParse(format!("PKCS#8 PEM encode: {e}")))
+}
+
+/// Parses a DER SubjectPublicKeyInfo.
+pub fn parse_public_key_der(der: &[u8]) -> Result<RsaPublicKey, CryptoError> {
+    let info = spki::SubjectPublicKeyInfoRef::from_der(der)
+        .map_err(|e| CryptoError::KeyParse(format!("SPKI DER: {e}")))?;
+    if info.algorithm.oid != pkcs1::ALGORITHM_OID {
+        return Err(CryptoError::KeyParse(format!(
+            "SPKI DER: algorithm {} is not RSA",
+            info.algorithm.oid
+        )));
+    }
+    let pkcs1_der = info
+        .subject_public_key
+        .as_bytes()
+        .ok_or_else(|| CryptoError::KeyParse("SPKI DER: key bit string has unused bits".into()))?;
+    parse_pkcs1_public_key_der(pkcs1_der)
+}
+
+/// Parses a PKCS#8 or PKCS#1 DER private key.
+pub fn parse_private_key_der(der: &[u8]) -> Result<RsaPrivateKey, CryptoError> {
+    // Same fallback order as the PEM path
+    let private_key = match RsaPrivateKey::from_pkcs8_der(der) {
+        Ok(k) => k,
+        Err(pkcs8_err) => RsaPrivateKey::from_pkcs1_der(der).map_err(|e| {
+            CryptoError::KeyParse(format!("PKCS#8 DER: {pkcs8_err}; PKCS#1 DER: {e}"))
+        })?,
+    };
+    check_private_key_size(private_key)
+}
+
+// Tolerant PEM reader for canonicalization: any line length, CRLF or LF, and
+// whitespace anywhere inside the armor. Returns the label and decoded body.
+fn decode_pem_lenient(pem: &str) -> Result<(&str, Zeroizing<Vec<u8>>), CryptoError> {
+    let malformed = |why: &str| CryptoError::KeyParse(format!("PEM: {why}"));
+
+    let rest = pem
+        .trim()
+        .strip_prefix("-----BEGIN ")
+        .ok_or_else(|| malformed("missing BEGIN line"))?;
+    let (label, rest) = rest
+        .split_once("-----")
+        .ok_or_else(|| malformed("unterminated BEGIN line"))?;
+    let end = format!("-----END {label}-----");
+    let body = rest
+        .strip_suffix(end.as_str())
+        .ok_or_else(|| malformed("missing or mismatched END line"))?;
+
+    let body: Zeroizing<String> =
+        Zeroizing::new(body.chars().filter(|c| !c.is_ascii_whitespace()).collect());
+    let der = STANDARD
+        .decode(body.as_bytes())
+        .map_err(|_| malformed("body is not valid base64"))?;
+    Ok((label, Zeroizing::new(der)))
+}
+
+/// Strict RFC 7468 decode of any PEM document, without interpreting the
+/// body. Returns the label (e.g. `CERTIFICATE REQUEST`) and the DER bytes.
+pub fn pem_decode(pem: &[u8]) -> Result<(String, Vec<u8>), CryptoError> {
+    let (label, der) =
+        pem_rfc7468::decode_vec(pem).map_err(|e| CryptoError::KeyParse(format!("PEM: {e}")))?;
+    Ok((label.to_string(), der))
+}
+
+/// Splits a bundle of concatenated PEM documents, such as a private key
+/// followed by its certificate chain, into the individual documents in order,
+/// each from its BEGIN line through its END line. Text between documents
+/// (e.g. OpenSSL's `Bag Attributes`) is ignored. A BEGIN line without a
+/// matching END line is `KeyParse`. The bodies are not decoded; pass each
+/// document to `pem_decode`.
+pub fn pem_split(bundle: &[u8]) -> Result<Vec<&[u8]>, CryptoError> {
+    const BEGIN: &[u8] = b"-----BEGIN ";
+    const DASHES: &[u8] = b"-----";
+    let find =
+        |haystack: &[u8], needle: &[u8]| haystack.windows(needle.len()).position(|w| w == needle);
+    let unterminated =
+        || CryptoError::KeyParse("PEM bundle: BEGIN line without a matching END line".into());
+
+    let mut blocks = Vec::new();
+    let mut rest = bundle;
+    while let Some(start) = find(rest, BEGIN) {
+        let block = &rest[start..];
+        let label_len = find(&block[BEGIN.len()..], DASHES).ok_or_else(unterminated)?;
+        let end_line = [
+            b"-----END ",
+            &block[BEGIN.len()..BEGIN.len() + label_len],
+            DASHES,
+        ]
+        .concat();
+        let end = find(block, &end_line).ok_or_else(unterminated)? + end_line.len();
+        blocks.push(&block[..end]);
+        rest = &block[end..];
+    }
+    Ok(blocks)
+}
+
+/// Wraps `der` in PEM armor with `label`, using 64-column LF-terminated lines.
+pub fn pem_encode(label: &str, der: &[u8]) -> Result<String, CryptoError> {
+    pem_rfc7468::encode_string(label, pem_rfc7468::LineEnding::LF, der).map_err(|_| {
+        CryptoError::InvalidArgument("PEM label must be printable ASCII without '-' at either end")
+    })
+}
+
+/// Re-emits an SPKI or PKCS#1 PEM public key as canonical SPKI PEM: 64-column
+/// base64, LF line endings and one trailing newline. The input may use any
+/// line length, CRLF endings or stray whitespace, but must decode to a valid
+/// key.
+pub fn canonicalize_public_key_pem(pem: &str) -> Result<String, CryptoError> {
+    parse_public_key_pem_lenient(pem)?
+        .to_public_key_pem(LineEnding::LF)
+        .map_err(|e| CryptoError::KeyParse(format!("SPKI PEM encode: {e}")))
+}
+
+/// Same as `parse_public_key_pem` but with the formatting tolerance of
+/// `canonicalize_public_key_pem`.
+pub fn parse_public_key_pem_lenient(pem: &str) -> Result<RsaPublicKey, CryptoError> {
+    let (label, der) = decode_pem_lenient(pem)?;
+    match label {
+        "PUBLIC KEY" => parse_public_key_der(&der),
+        "RSA PUBLIC KEY" => parse_pkcs1_public_key_der(&der),
+        other => Err(CryptoError::KeyParse(format!(
+            "PEM: unexpected label {other:?} for a public key"
+        ))),
+    }
+}
+
+/// Same as `canonicalize_public_key_pem` for a PKCS#8 or PKCS#1 private key,
+/// emitting PKCS#8 PEM.
+pub fn canonicalize_private_key_pem(pem: &str) -> Result<Zeroizing<String>, CryptoError> {
+    let (label, der) = decode_pem_lenient(pem)?;
+    if label != "PRIVATE KEY" && label != "RSA PRIVATE KEY" {
+        return Err(CryptoError::KeyParse(format!(
+            "PEM: unexpected label {label:?} for a private key"
+        )));
+    }
+    parse_private_key_der(&der)?
+        .to_pkcs8_pem(LineEnding::LF)
+        .map_err(|e| CryptoError::KeyParse(format!("PKCS#8 PEM encode: {e}")))
+}
+
+/// On-the-wire encoding of an RSA public key.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PublicKeyFormat {
+    SpkiPem,
+    Pkcs1Pem,
+    SpkiDer,
+    Pkcs1Der,
+}
+
+// Big enough for the DER of a 16384-bit key; PEM bodies are decoded into it so
+// probing stays off the heap.
+const PROBE_BUF_LEN: usize = 4096;
+
+/// Reports which encoding `bytes` holds an RSA public key in, without building
+/// the key or allocating. Returns `None` if no supported encoding parses.
+pub fn detect_public_key_format(bytes: &[u8]) -> Option<PublicKeyFormat> {
+    if let Ok(label) = spki::der::pem::decode_label(bytes) {
+        let mut buf = [0u8; PROBE_BUF_LEN];
+        let (_, der) = spki::der::pem::decode(bytes, &mut buf).ok()?;
+        return match label {
+            "PUBLIC KEY" if is_rsa_spki_der(der) => Some(PublicKeyFormat::SpkiPem),
+            "RSA PUBLIC KEY" if is_pkcs1_public_der(der) => Some(PublicKeyFormat::Pkcs1Pem),
+            _ => None,
+        };
+    }
+
+    if is_rsa_spki_der(bytes) {
+        Some(PublicKeyFormat::SpkiDer)
+    } else if is_pkcs1_public_der(bytes) {
+        Some(PublicKeyFormat::Pkcs1Der)
+    } else {
+        None
+    }
+}
+
+fn is_rsa_spki_der(der: &[u8]) -> bool {
+    match spki::SubjectPublicKeyInfoRef::from_der(der) {
+        Ok(info) => {
+            info.algorithm.oid == pkcs1::ALGORITHM_OID
+                && info
+                    .subject_public_key
+                    .as_bytes()
+                    .is_some_and(is_pkcs1_public_der)
+        }
+        Err(_) => false,
+    }
+}
+
+fn is_pkcs1_public_der(der: &[u8]) -> bool {
+    pkcs1::RsaPublicKey::from_der(der).is_ok()
+}
+
+/// Smallest modulus `public_key_from_components` accepts.
+pub const RSA_MIN_RAW_MODULUS_BITS: usize = 1024;
+
+/// Builds a public key from a raw big-endian modulus and exponent. Even moduli
+/// and moduli under `RSA_MIN_RAW_MODULUS_BITS` are rejected.
+pub fn public_key_from_components(n: &[u8], e: &[u8]) -> Result<RsaPublicKey, CryptoError> {
+    // Big-endian, so the last byte holds the low bit
+    if n.last().is_none_or(|b| b & 1 == 0) {
+        return Err(CryptoError::KeyParse("RSA modulus is even".into()));
+    }
+
+    let n = BigUint::from_bytes_be(n);
+    let e = BigUint::from_bytes_be(e);
+
+    if n.bits() < RSA_MIN_RAW_MODULUS_BITS {
+        return Err(CryptoError::KeyParse(format!(
+            "RSA modulus is {} bits, minimum is {RSA_MIN_RAW_MODULUS_BITS}",
+            n.bits()
+        )));
+    }
+
+    rsa_public_key(n, e).map_err(|e| CryptoError::KeyParse(format!("RSA components: {e}")))
+}
+
+/// Builds a private key from raw big-endian `n`, `e`, `d`, `p` and `q`.
+/// `rsa` checks that `n = p*q` and that `d` inverts `e` modulo each `p - 1`;
+/// the CRT values are then precomputed. Any failure is `InconsistentKey`,
+/// except a modulus over `RSA_MAX_MODULUS_BITS`, which is `KeyParse` and is
+/// rejected before any key math.
+pub fn private_key_from_components(
+    n: &[u8],
+    e: &[u8],
+    d: &[u8],
+    p: &[u8],
+    q: &[u8],
+) -> Result<RsaPrivateKey, CryptoError> {
+    let n = BigUint::from_bytes_be(n);
+    if n.bits() > RSA_MAX_MODULUS_BITS {
+        return Err(CryptoError::KeyParse(format!(
+            "RSA modulus is {} bits, maximum is {RSA_MAX_MODULUS_BITS}",
+            n.bits()
+        )));
+    }
+
+    let primes = vec![BigUint::from_bytes_be(p), BigUint::from_bytes_be(q)];
+    let mut private_key = RsaPrivateKey::from_components(
+        n,
+        BigUint::from_bytes_be(e),
+        BigUint::from_bytes_be(d),
+        primes,
+    )
+    .map_err(|e| CryptoError::InconsistentKey(e.to_string()))?;
+    private_key
+        .precompute()
+        .map_err(|e| CryptoError::InconsistentKey(format!("CRT precompute: {e}")))?;
+    Ok(private_key)
+}
+
+/// SHA-256 of the key's canonical DER SubjectPublicKeyInfo encoding, so the
+/// same key always has the same fingerprint however it was originally encoded.
+pub fn public_key_fingerprint(public_key: &RsaPublicKey) -> Result<[u8; 32], CryptoError> {
+    let spki_der = public_key
+        .to_public_key_der()
+        .map_err(|e| CryptoError::KeyParse(format!("SPKI DER encode: {e}")))?;
+    Ok(Sha256::digest(spki_der.as_bytes()).into())
+}
+
+/// RFC 7638 JWK thumbprint: the base64url (no padding) SHA-256 of the canonical
+/// JWK `{"e":...,"kty":"RSA","n":...}`, members in lexicographic order with no
+/// whitespace. `n` and `e` are base64url of the unsigned big-endian integer
+/// with leading zero bytes stripped.
+pub fn jwk_thumbprint(public_key: &RsaPublicKey) -> String {
+    let canonical = format!(
+        r#"{{"e":"{}","kty":"RSA","n":"{}"}}"#,
+        URL_SAFE_NO_PAD.encode(public_key.e().to_bytes_be()),
+        URL_SAFE_NO_PAD.encode(public_key.n().to_bytes_be())
+    );
+    URL_SAFE_NO_PAD.encode(Sha256::digest(canonical.as_bytes()))
+}
+
+/// Parses an RSA public key from JWK JSON. Malformed JSON is `InvalidJson`; a
+/// `kty` other than `"RSA"` or a missing or non-base64url `n`/`e` is
+/// `InvalidJwk`. Leading zero bytes in `n` and `e` are tolerated. Other members
+/// are ignored.
+pub fn parse_public_key_jwk(json: &[u8]) -> Result<RsaPublicKey, CryptoError> {
+    let jwk: serde_json::Value = serde_json::from_slice(json).map_err(CryptoError::InvalidJson)?;
+    if jwk.get("kty").and_then(|v| v.as_str()) != Some("RSA") {
+        return Err(CryptoError::InvalidJwk("kty is not \"RSA\""));
+    }
+
+    let member = |name: &str,
+                  missing: &'static str,
+                  malformed: &'static str|
+     -> Result<BigUint, CryptoError> {
+        let b64 = jwk
+            .get(name)
+            .and_then(|v| v.as_str())
+            .ok_or(CryptoError::InvalidJwk(missing))?;
+        let bytes = URL_SAFE_NO_PAD
+            .decode(b64)
+            .map_err(|_| CryptoError::InvalidJwk(malformed))?;
+        Ok(BigUint::from_bytes_be(&bytes))
+    };
+    let n = member("n", "missing n", "n is not base64url")?;
+    let e = member("e", "missing e", "e is not base64url")?;
+
+    rsa_public_key(n, e).map_err(|e| CryptoError::KeyParse(format!("JWK: {e}")))
+}
+
+/// Parses an OpenSSH `ssh-rsa AAAA... [comment]` public key line, as found in
+/// `authorized_keys` or `id_rsa.pub`. Other key types are `UnsupportedAlgId`;
+/// anything that is not a well-formed OpenSSH key is `KeyParse`.
+pub fn parse_public_key_openssh(line: &[u8]) -> Result<RsaPublicKey, CryptoError> {
+    let line = std::str::from_utf8(line)?;
+    let key = ssh_key::PublicKey::from_openssh(line.trim())
+        .map_err(|e| CryptoError::KeyParse(format!("OpenSSH public key: {e}")))?;
+    let rsa = match key.key_data() {
+        ssh_key::public::KeyData::Rsa(rsa) => rsa,
+        _ => {
+            return Err(CryptoError::UnsupportedAlgId(format!(
+                "OpenSSH key type {}",
+                key.algorithm()
+            )))
+        }
+    };
+
+    let mpint = |m: &ssh_key::Mpint| {
+        m.as_positive_bytes()
+            .map(BigUint::from_bytes_be)
+            .ok_or_else(|| {
+                CryptoError::KeyParse("OpenSSH RSA key has a negative or zero component".into())
+            })
+    };
+    rsa_public_key(mpint(&rsa.n)?, mpint(&rsa.e)?)
+        .map_err(|e| CryptoError::KeyParse(format!("OpenSSH public key: {e}")))
+}
+
+/// Emits the key as a JWK for an RS256 signing key:
+/// `{"kty":"RSA","n":...,"e":...,"alg":"RS256","use":"sig"}` plus `"kid"` when
+/// given, with `n` and `e` encoded as in `jwk_thumbprint`.
+pub fn public_key_to_jwk(public_key: &RsaPublicKey, kid: Option<&str>) -> String {
+    let mut jwk = format!(
+        r#"{{"kty":"RSA","n":"{}","e":"{}","alg":"RS256","use":"sig""#,
+        URL_SAFE_NO_PAD.encode(public_key.n().to_bytes_be()),
+        URL_SAFE_NO_PAD.encode(public_key.e().to_bytes_be())
+    );
+    if let Some(kid) = kid {
+        jwk.push_str(r#","kid":"#);
+        jwk.push_str(&serde_json::Value::from(kid).to_string());
+    }
+    jwk.push('}');
+    jwk
+}
+
+/// RSA encryption padding schemes, for sizing plaintexts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RsaPadding {
+    /// OAEP with SHA-256 for both the label hash and MGF1, as `rsa_encrypt_oaep` uses.
+    OaepSha256,
+    /// PKCS#1 v1.5 encryption padding.
+    Pkcs1v15,
+}
+
+impl RsaPadding {
+    pub(crate) fn from_i32(padding: i32) -> Option<RsaPadding> {
+        match padding {
+            RSA_PADDING_OAEP_SHA256 => Some(RsaPadding::OaepSha256),
+            RSA_PADDING_PKCS1V15 => Some(RsaPadding::Pkcs1v15),
+            _ => None,
+        }
+    }
+}
+
+/// Largest plaintext that fits in one RSA block for a `k`-byte modulus:
+/// `k - 2*hLen - 2` for OAEP (`k - 66` with SHA-256) and `k - 11` for
+/// PKCS#1 v1.5. Zero if the key is too small for the padding.
+pub fn rsa_max_message_len(public_key: &RsaPublicKey, padding: RsaPadding) -> usize {
+    let k = public_key.size();
+    match padding {
+        RsaPadding::OaepSha256 => k.saturating_sub(2 * 32 + 2),
+        RsaPadding::Pkcs1v15 => k.saturating_sub(11),
+    }
+}
+
+/// Encrypts `plaintext` of any length as a run of RSA-OAEP (SHA-256) blocks:
+/// the plaintext is split into `rsa_max_message_len` pieces and each
+/// `k`-byte ciphertext block is appended in order. An empty plaintext still
+/// produces one block. Every block costs a full RSA operation, so this is for
+/// payloads a few blocks long; bulk data belongs under a symmetric key.
+pub fn rsa_encrypt_chunked<R: CryptoRngCore>(
+    rng: &mut R,
+    public_key: &RsaPublicKey,
+    plaintext: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    let chunk_len = rsa_max_message_len(public_key, RsaPadding::OaepSha256);
+    if chunk_len == 0 {
+        return Err(CryptoError::Crypto(rsa::Error::MessageTooLong));
+    }
+
+    let mut chunks: Vec<&[u8]> = plaintext.chunks(chunk_len).collect();
+    if chunks.is_empty() {
+        chunks.push(&[]);
+    }
+
+    let mut out = Vec::with_capacity(chunks.len() * public_key.size());
+    for chunk in chunks {
+        out.extend(
+            public_key
+                .encrypt(rng, Oaep::new::<Sha256>(), chunk)
+                .map_err(CryptoError::Crypto)?,
+        );
+    }
+    Ok(out)
+}
+
+/// Reverses `rsa_encrypt_chunked`. The ciphertext must be a non-zero multiple
+/// of the modulus size; any block that fails to decrypt fails the whole call.
+pub fn rsa_decrypt_chunked<R: CryptoRngCore>(
+    rng: &mut R,
+    private_key: &RsaPrivateKey,
+    ciphertext: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    let k = private_key.size();
+    if ciphertext.is_empty() || !ciphertext.len().is_multiple_of(k) {
+        return Err(CryptoError::InvalidLength {
+            what: "chunked RSA ciphertext",
+            expected: ciphertext.len().div_ceil(k).max(1) * k,
+            actual: ciphertext.len(),
+        });
+    }
+
+    let mut out = Vec::with_capacity(ciphertext.len());
+    for block in ciphertext.chunks(k) {
+        out.extend(
+            private_key
+                .decrypt_blinded(rng, Oaep::new::<Sha256>(), block)
+                .map_err(CryptoError::Crypto)?,
+        );
+    }
+    Ok(out)
+}
+
+/// Decrypts a PKCS#1 v1.5 ciphertext whose plaintext must be exactly
+/// `out.len()` bytes, without a padding oracle, in the style of TLS premaster
+/// secret handling (RFC 5246 section 7.4.7.1). `out` is always filled: with
+/// the plaintext if the padding is valid and the message is `out.len()` bytes
+/// long, and with random bytes otherwise. The returned `Choice` says which.
+/// The padding check and the selection are constant time, and both outcomes
+/// run the same code; with the length fixed in advance every check is at a
+/// fixed position.
+///
+/// Errors only come from public inputs: a ciphertext that is not the modulus
+/// size or not below the modulus is `InvalidLength`/`Crypto`, and an
+/// `out.len()` over `k - 11` is `InvalidArgument`. The RSA operation itself
+/// is blinded but uses `rsa`'s big-integer arithmetic, which is not
+/// guaranteed constant time (RUSTSEC-2023-0071).
+pub fn rsa_decrypt_pkcs1v15_ct<R: CryptoRngCore>(
+    rng: &mut R,
+    private_key: &RsaPrivateKey,
+    ciphertext: &[u8],
+    out: &mut [u8],
+) -> Result<Choice, CryptoError> {
+    let k = private_key.size();
+    if ciphertext.len() != k {
+        return Err(CryptoError::InvalidLength {
+            what: "RSA ciphertext",
+            expected: k,
+            actual: ciphertext.len(),
+        });
+    }
+    let msg_len = out.len();
+    if msg_len + 11 > k {
+        return Err(CryptoError::InvalidArgument(
+            "expected plaintext length exceeds k - 11",
+        ));
+    }
+
+    let mut fallback = Zeroizing::new(vec![0u8; msg_len]);
+    rng.fill_bytes(&mut fallback);
+
+    let c = BigUint::from_bytes_be(ciphertext);
+    let m = Zeroizing::new(
+        rsa::hazmat::rsa_decrypt_and_check(private_key, Some(rng), &c)
+            .map_err(CryptoError::Crypto)?
+            .to_bytes_be(),
+    );
+    let mut em = Zeroizing::new(vec![0u8; k]);
+    em[k - m.len()..].copy_from_slice(&m);
+
+    // EM = 0x00 || 0x02 || PS (k - msg_len - 3 nonzero bytes) || 0x00 || M
+    let separator = k - msg_len - 1;
+    let mut valid = em[0].ct_eq(&0x00) & em[1].ct_eq(&0x02) & em[separator].ct_eq(&0x00);
+    for b in &em[2..separator] {
+        valid &= !b.ct_eq(&0x00);
+    }
+
+    for ((o, m), f) in out
+        .iter_mut()
+        .zip(&em[separator + 1..])
+        .zip(fallback.iter())
+    {
+        *o = u8::conditional_select(f, m, valid);
+    }
+    Ok(valid)
+}
+
+/// Verifies a PKCS#1 v1.5 signature over the contents of the file at `path`,
+/// streaming it through the hash rather than loading it. A file that cannot
+/// be opened or read is `Io`.
+pub fn verify_file_pkcs1v15(
+    public_key: &RsaPublicKey,
+    hash_alg: HashAlg,
+    path: &Path,
+    sig: &[u8],
+) -> Result<(), CryptoError> {
+    let file = File::open(path).map_err(CryptoError::Io)?;
+    let digest = hash_alg.digest_reader(file).map_err(CryptoError::Io)?;
+    verify_pkcs1v15_prehashed(public_key, hash_alg, &digest, sig)
+}
+
+/// How often, in bytes hashed, `verify_file_pkcs1v15_progress` reports.
+pub const FILE_PROGRESS_INTERVAL: u64 = 1024 * 1024;
+
+// Counts bytes as they are read and reports every `FILE_PROGRESS_INTERVAL`.
+struct ProgressReader<R, F> {
+    inner: R,
+    progress: F,
+    processed: u64,
+    reported: u64,
+    total: u64,
+}
+
+impl<R: Read, F: FnMut(u64, u64)> Read for ProgressReader<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.processed += n as u64;
+        if n == 0 || self.processed - self.reported >= FILE_PROGRESS_INTERVAL {
+            self.reported = self.processed;
+            (self.progress)(self.processed, self.total);
+        }
+        Ok(n)
+    }
+}
+
+/// Same as `verify_file_pkcs1v15` but calls `progress(processed, total)`
+/// while hashing: after each `FILE_PROGRESS_INTERVAL` bytes and once more at
+/// end of file. `total` is the file's size when it was opened. Progress is
+/// only reported, so `progress` cannot stop or fail the hashing.
+pub fn verify_file_pkcs1v15_progress(
+    public_key: &RsaPublicKey,
+    hash_alg: HashAlg,
+    path: &Path,
+    sig: &[u8],
+    progress: impl FnMut(u64, u64),
+) -> Result<(), CryptoError> {
+    let file = File::open(path).map_err(CryptoError::Io)?;
+    let total = file.metadata().map_err(CryptoError::Io)?.len();
+    let reader = ProgressReader {
+        inner: file,
+        progress,
+        processed: 0,
+        reported: 0,
+        total,
+    };
+    let digest = hash_alg.digest_reader(reader).map_err(CryptoError::Io)?;
+    verify_pkcs1v15_prehashed(public_key, hash_alg, &digest, sig)
+}
+
+/// Whether `public_key` is the public half of `private_key` (same modulus and
+/// public exponent).
+pub fn keys_match(private_key: &RsaPrivateKey, public_key: &RsaPublicKey) -> bool {
+    private_key.n() == public_key.n() && private_key.e() == public_key.e()
+}
+
+// The openssl-blacklist fingerprint of a modulus: the last 80 bits of the
+// SHA-1 of `openssl rsa -modulus` output, as lowercase hex.
+fn debian_weak_key_fingerprint(n: &BigUint) -> String {
+    let mut line = String::from("Modulus=");
+    for b in n.to_bytes_be() {
+        line.push_str(&format!("{b:02X}"));
+    }
+    line.push('\n');
+    Sha1::digest(line.as_bytes())[10..]
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect()
+}
+
+/// Heuristic screen for obviously weak RSA moduli: true if the modulus is
+/// even, shares a listed factor, or appears in a Debian openssl-blacklist.
+/// `blocklist` has one entry per line, with blank lines and `#` comments
+/// ignored. A line of exactly 20 hex digits is an openssl-blacklist
+/// fingerprint; any other hex line (optionally `0x`-prefixed) is a known-bad
+/// prime or modulus that must not divide the key's modulus, so an 80-bit
+/// divisor has to be written with a `0x` prefix or a leading zero.
+///
+/// This is not a factoring attempt: a key that passes may still be weak.
+pub fn rsa_modulus_is_weak(
+    public_key: &RsaPublicKey,
+    blocklist: &str,
+) -> Result<bool, CryptoError> {
+    let n = public_key.n();
+    // `rsa` rejects even moduli when building keys today; checked here anyway
+    // so the screen does not depend on that.
+    if n.to_bytes_be().last().is_some_and(|b| b & 1 == 0) {
+        return Ok(true);
+    }
+
+    let mut fingerprint = None;
+    for line in blocklist.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.len() == 20 && line.bytes().all(|b| b.is_ascii_hexdigit()) {
+            let fingerprint = fingerprint.get_or_insert_with(|| debian_weak_key_fingerprint(n));
+            if line.eq_ignore_ascii_case(fingerprint) {
+                return Ok(true);
+            }
+            continue;
+        }
+
+        let hex = line.strip_prefix("0x").unwrap_or(line);
+        let divisor = BigUint::parse_bytes(hex.as_bytes(), 16)
+            .ok_or(CryptoError::InvalidArgument("blocklist entry is not hex"))?;
+        if divisor.bits() < 2 {
+            return Err(CryptoError::InvalidArgument(
+                "blocklist entry must be at least 2",
+            ));
+        }
+        if (n % &divisor).bits() == 0 {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Signs `payload` with PKCS#1 v1.5 and SHA-256, the scheme `sign_payload`
+/// uses by default.
+pub fn sign(payload: &[u8], priv_key_pem: &str) -> Result<Vec<u8>, CryptoError> {
+    sign_with_rng(&mut LibRng, payload, priv_key_pem)
+}
+
+/// Same as `sign` but draws blinding randomness from `rng` instead of the
+/// library RNG, so tests can run the exact signing path with a seeded
+/// generator.
+pub fn sign_with_rng<R: CryptoRngCore>(
+    rng: &mut R,
+    payload: &[u8],
+    priv_key_pem: &str,
+) -> Result<Vec<u8>, CryptoError> {
+    let private_key = parse_private_key_pem(priv_key_pem)?;
+    sign_pkcs1v15_prehashed_with_rng(
+        rng,
+        &private_key,
+        HashAlg::Sha256,
+        &HashAlg::Sha256.digest(payload),
+    )
+}
+
+/// Verifies a PKCS#1 v1.5 SHA-256 signature over `payload`.
+pub fn verify(payload: &[u8], sig: &[u8], pub_key_pem: &str) -> Result<(), CryptoError> {
+    let public_key = parse_public_key_pem(pub_key_pem)?;
+    verify_pkcs1v15(&public_key, HashAlg::Sha256, payload, sig)
+}
+
+pub fn sign_pkcs1v15(
+    private_key: &RsaPrivateKey,
+    hash_alg: HashAlg,
+    payload: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    sign_pkcs1v15_prehashed(private_key, hash_alg, &hash_alg.digest(payload))
+}
+
+// PKCS#1 v1.5 signatures are deterministic, so the RNG never changes the output;
+// it only drives blinding of the private-key operation. `sign` without an RNG
+// skips blinding and leaks timing on the secret exponent.
+pub fn sign_pkcs1v15_prehashed(
+    private_key: &RsaPrivateKey,
+    hash_alg: HashAlg,
+    hashed: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    sign_pkcs1v15_prehashed_with_rng(&mut LibRng, private_key, hash_alg, hashed)
+}
+
+// Domain-separated signing input: `context || 0x00 || payload`, hashed with
+// `hash_alg`. Contexts may not contain NUL, so the first 0x00 always ends the
+// context and ("a", "bc") can never collide with ("ab", "c").
+fn context_digest(
+    hash_alg: HashAlg,
+    context: &[u8],
+    payload: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    if context.contains(&0) {
+        return Err(CryptoError::InvalidArgument(
+            "signing context must not contain NUL bytes",
+        ));
+    }
+    Ok(hash_alg.digest_segments([context, &[0u8][..], payload]))
+}
+
+/// Signs `context || 0x00 || payload`, binding the signature to a context
+/// label so one made for one purpose does not verify for another. `context`
+/// must not contain NUL.
+pub fn sign_pkcs1v15_ctx(
+    private_key: &RsaPrivateKey,
+    hash_alg: HashAlg,
+    context: &[u8],
+    payload: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    sign_pkcs1v15_prehashed(
+        private_key,
+        hash_alg,
+        &context_digest(hash_alg, context, payload)?,
+    )
+}
+
+/// Verifies a signature made by `sign_pkcs1v15_ctx` with the same context.
+pub fn verify_pkcs1v15_ctx(
+    public_key: &RsaPublicKey,
+    hash_alg: HashAlg,
+    context: &[u8],
+    payload: &[u8],
+    sig: &[u8],
+) -> Result<(), CryptoError> {
+    verify_pkcs1v15_prehashed(
+        public_key,
+        hash_alg,
+        &context_digest(hash_alg, context, payload)?,
+        sig,
+    )
+}
+
+/// Signs `timestamp.to_be_bytes() || payload`, so the signature only
+/// verifies for the timestamp it was made with.
+pub fn sign_pkcs1v15_timestamped(
+    private_key: &RsaPrivateKey,
+    hash_alg: HashAlg,
+    timestamp: i64,
+    payload: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    sign_pkcs1v15_prehashed(
+        private_key,
+        hash_alg,
+        &hash_alg.digest_segments([&timestamp.to_be_bytes()[..], payload]),
+    )
+}
+
+/// Verifies a signature made by `sign_pkcs1v15_timestamped`. The skew window
+/// is checked first and is inclusive: `|now - timestamp| == max_skew_secs`
+/// is accepted.
+pub fn verify_pkcs1v15_timestamped(
+    public_key: &RsaPublicKey,
+    hash_alg: HashAlg,
+    timestamp: i64,
+    payload: &[u8],
+    sig: &[u8],
+    max_skew_secs: u64,
+    now: i64,
+) -> Result<(), CryptoError> {
+    if now.abs_diff(timestamp) > max_skew_secs {
+        return Err(CryptoError::TimestampSkew {
+            timestamp,
+            now,
+            max_skew_secs,
+        });
+    }
+    verify_pkcs1v15_prehashed(
+        public_key,
+        hash_alg,
+        &hash_alg.digest_segments([&timestamp.to_be_bytes()[..], payload]),
+        sig,
+    )
+}
+
+pub const KID_LEN: usize = 4;
+
+/// Short key identifier: the first `KID_LEN` bytes of `public_key_fingerprint`.
+/// It only routes to a candidate key; it is not a security boundary.
+pub fn key_id(public_key: &RsaPublicKey) -> Result<[u8; KID_LEN], CryptoError> {
+    let fingerprint = public_key_fingerprint(public_key)?;
+    Ok(fingerprint[..KID_LEN]
+        .try_into()
+        .expect("fingerprint is longer than a kid"))
+}
+
+/// Signs `payload` with PKCS#1 v1.5 and prefixes the signer's key id:
+/// `kid (KID_LEN bytes) || signature`. The kid is not covered by the
+/// signature.
+pub fn sign_pkcs1v15_with_kid(
+    private_key: &RsaPrivateKey,
+    hash_alg: HashAlg,
+    payload: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    let mut framed = key_id(&private_key.to_public_key())?.to_vec();
+    framed.extend_from_slice(&sign_pkcs1v15(private_key, hash_alg, payload)?);
+    Ok(framed)
+}
+
+/// Verifies a `sign_pkcs1v15_with_kid` blob against whichever of
+/// `public_keys` carry its kid, trying each of them if several collide. A
+/// kid that matches no key is `SignatureMismatch`.
+pub fn verify_pkcs1v15_with_kid(
+    public_keys: &[RsaPublicKey],
+    hash_alg: HashAlg,
+    payload: &[u8],
+    framed: &[u8],
+) -> Result<(), CryptoError> {
+    if framed.len() < KID_LEN {
+        return Err(CryptoError::MalformedToken(
+            "kid-framed signature is shorter than the kid",
+        ));
+    }
+    let (kid, sig) = framed.split_at(KID_LEN);
+
+    let digest = hash_alg.digest(payload);
+    for public_key in public_keys {
+        if key_id(public_key)? == kid
+            && verify_pkcs1v15_prehashed(public_key, hash_alg, &digest, sig).is_ok()
+        {
+            return Ok(());
+        }
+    }
+    Err(CryptoError::SignatureMismatch)
+}
+
+/// First four bytes of every sealed signature container.
+pub const SEALED_MAGIC: [u8; 4] = *b"RCSG";
+/// The only container version `open_signature` understands.
+pub const SEALED_VERSION: u8 = 1;
+
+/// Signature scheme recorded in a sealed signature container.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SealedAlg {
+    /// RSA PKCS#1 v1.5 with SHA-256.
+    Rs256,
+    /// RSA PKCS#1 v1.5 with SHA-384.
+    Rs384,
+    /// RSA PKCS#1 v1.5 with SHA-512.
+    Rs512,
+    /// RSA-PSS with SHA-256, MGF1-SHA-256 and a 32-byte salt.
+    Ps256,
+    /// Ed25519.
+    EdDsa,
+}
+
+impl SealedAlg {
+    pub(crate) fn from_u32(alg: u32) -> Option<SealedAlg> {
+        match alg {
+            SEALED_ALG_RS256 => Some(SealedAlg::Rs256),
+            SEALED_ALG_RS384 => Some(SealedAlg::Rs384),
+            SEALED_ALG_RS512 => Some(SealedAlg::Rs512),
+            SEALED_ALG_PS256 => Some(SealedAlg::Ps256),
+            SEALED_ALG_EDDSA => Some(SealedAlg::EdDsa),
+            _ => None,
+        }
+    }
+
+    /// The `SEALED_ALG_*` value stored in the container's alg byte.
+    pub fn id(self) -> u8 {
+        let id = match self {
+            SealedAlg::Rs256 => SEALED_ALG_RS256,
+            SealedAlg::Rs384 => SEALED_ALG_RS384,
+            SealedAlg::Rs512 => SEALED_ALG_RS512,
+            SealedAlg::Ps256 => SEALED_ALG_PS256,
+            SealedAlg::EdDsa => SEALED_ALG_EDDSA,
+        };
+        id as u8
+    }
+}
+
+/// A parsed sealed signature container, borrowing from the container bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SealedSignature<'a> {
+    pub alg: SealedAlg,
+    pub kid: &'a [u8],
+    pub sig: &'a [u8],
+}
+
+/// Builds a sealed signature container:
+/// `magic(4) || version(1) || alg(1) || kid_len(1) || kid || sig_len(2, BE) || sig`.
+/// The kid may be empty; it is limited to 255 bytes and the signature to
+/// 65535, and neither is covered by the signature.
+pub fn seal_signature(alg: SealedAlg, kid: &[u8], sig: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    let kid_len = u8::try_from(kid.len()).map_err(|_| {
+        CryptoError::InvalidArgument("sealed signature kid is longer than 255 bytes")
+    })?;
+    let sig_len = u16::try_from(sig.len())
+        .map_err(|_| CryptoError::InvalidArgument("sealed signature is longer than 65535 bytes"))?;
+    if sig.is_empty() {
+        return Err(CryptoError::InvalidArgument("sealed signature is empty"));
+    }
+
+    let mut container = Vec::with_capacity(SEALED_MAGIC.len() + 5 + kid.len() + sig.len());
+    container.extend_from_slice(&SEALED_MAGIC);
+    container.push(SEALED_VERSION);
+    container.push(alg.id());
+    container.push(kid_len);
+    container.extend_from_slice(kid);
+    container.extend_from_slice(&sig_len.to_be_bytes());
+    container.extend_from_slice(sig);
+    Ok(container)
+}
+
+/// Parses a `seal_signature` container. Truncation, a wrong magic, an unknown
+/// version, an empty signature and trailing bytes are `MalformedToken`; an
+/// unknown alg is `UnsupportedAlgId`.
+pub fn open_signature(container: &[u8]) -> Result<SealedSignature<'_>, CryptoError> {
+    const TRUNCATED: CryptoError = CryptoError::MalformedToken("sealed signature is truncated");
+
+    let (magic, rest) = container.split_first_chunk::<4>().ok_or(TRUNCATED)?;
+    if *magic != SEALED_MAGIC {
+        return Err(CryptoError::MalformedToken(
+            "not a sealed signature (bad magic)",
+        ));
+    }
+    let (&[version, alg, kid_len], rest) = rest.split_first_chunk::<3>().ok_or(TRUNCATED)?;
+    if version != SEALED_VERSION {
+        return Err(CryptoError::MalformedToken(
+            "unsupported sealed signature version",
+        ));
+    }
+    let alg = SealedAlg::from_u32(u32::from(alg))
+        .ok_or_else(|| CryptoError::UnsupportedAlgId(format!("sealed signature alg {alg}")))?;
+
+    let (kid, rest) = rest
+        .split_at_checked(usize::from(kid_len))
+        .ok_or(TRUNCATED)?;
+    let (sig_len, sig) = rest.split_first_chunk::<2>().ok_or(TRUNCATED)?;
+    let sig_len = usize::from(u16::from_be_bytes(*sig_len));
+    if sig.len() < sig_len {
+        return Err(TRUNCATED);
+    }
+    if sig.len() > sig_len {
+        return Err(CryptoError::MalformedToken(
+            "trailing bytes after sealed signature",
+        ));
+    }
+    if sig.is_empty() {
+        return Err(CryptoError::MalformedToken("sealed signature is empty"));
+    }
+
+    Ok(SealedSignature { alg, kid, sig })
+}
+
+/// Signs `payload` twice with PKCS#1 v1.5, once over SHA-256 and once over
+/// SHA-512, and returns `sig_sha256 || sig_sha512`. Both halves are exactly the
+/// modulus size with no length prefix or header, so the blob is always twice
+/// the key size.
+pub fn sign_pkcs1v15_dual(
+    private_key: &RsaPrivateKey,
+    payload: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    let mut blob = sign_pkcs1v15(private_key, HashAlg::Sha256, payload)?;
+    blob.extend_from_slice(&sign_pkcs1v15(private_key, HashAlg::Sha512, payload)?);
+    Ok(blob)
+}
+
+/// Verifies a `sign_pkcs1v15_dual` blob. With `require_both` both halves must
+/// verify; otherwise either one is enough. A blob that is not twice the
+/// modulus size is `InvalidLength`.
+pub fn verify_pkcs1v15_dual(
+    public_key: &RsaPublicKey,
+    payload: &[u8],
+    blob: &[u8],
+    require_both: bool,
+) -> Result<(), CryptoError> {
+    let size = public_key.size();
+    if blob.len() != 2 * size {
+        return Err(CryptoError::InvalidLength {
+            what: "dual signature",
+            expected: 2 * size,
+            actual: blob.len(),
+        });
+    }
+    let (sig_sha256, sig_sha512) = blob.split_at(size);
+
+    let sha256_ok = verify_pkcs1v15(public_key, HashAlg::Sha256, payload, sig_sha256).is_ok();
+    let sha512_ok = verify_pkcs1v15(public_key, HashAlg::Sha512, payload, sig_sha512).is_ok();
+    let ok = if require_both {
+        sha256_ok && sha512_ok
+    } else {
+        sha256_ok || sha512_ok
+    };
+    if ok {
+        Ok(())
+    } else {
+        Err(CryptoError::SignatureMismatch)
+    }
+}
+
+/// Length of the SHA-256 digest that opens a `sign_pkcs1v15_with_digest` record.
+pub const RECORD_DIGEST_LEN: usize = 32;
+
+/// Signs `payload` with PKCS#1 v1.5 over SHA-256 and returns the
+/// self-contained record `sha256(payload) || sig`, so the signature can be
+/// re-checked later without the payload.
+pub fn sign_pkcs1v15_with_digest(
+    private_key: &RsaPrivateKey,
+    payload: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    let mut record = HashAlg::Sha256.digest(payload);
+    record.extend_from_slice(&sign_pkcs1v15_prehashed(
+        private_key,
+        HashAlg::Sha256,
+        &record,
+    )?);
+    Ok(record)
+}
+
+/// Verifies a `sign_pkcs1v15_with_digest` record: the signature must cover
+/// the digest stored in front of it. Success shows that the key signed that
+/// digest; nothing authenticates the digest apart from the signature, and no
+/// payload is checked against it. A record that is not
+/// `RECORD_DIGEST_LEN` plus the modulus size is `InvalidLength`.
+pub fn verify_pkcs1v15_digest_record(
+    public_key: &RsaPublicKey,
+    record: &[u8],
+) -> Result<(), CryptoError> {
+    let expected = RECORD_DIGEST_LEN + public_key.size();
+    if record.len() != expected {
+        return Err(CryptoError::InvalidLength {
+            what: "digest and signature record",
+            expected,
+            actual: record.len(),
+        });
+    }
+    let (digest, sig) = record.split_at(RECORD_DIGEST_LEN);
+    verify_pkcs1v15_prehashed(public_key, HashAlg::Sha256, digest, sig)
+}
+
+pub const MAC_TAG_LEN: usize = 32;
+
+fn frame_mac(mac_key: &[u8], payload: &[u8], framed_sig: &[u8]) -> Hmac<Sha256> {
+    let mut mac =
+        <Hmac<Sha256> as Mac>::new_from_slice(mac_key).expect("HMAC accepts keys of any length");
+    mac.update(payload);
+    mac.update(framed_sig);
+    mac
+}
+
+/// Signs `payload` with PKCS#1 v1.5 and appends an HMAC-SHA256 tag for cheap
+/// transport integrity checks. The blob is `sig_len (2 bytes, big-endian) ||
+/// sig || tag`, where the 32-byte tag is HMAC-SHA256 under `mac_key` of
+/// `payload || sig_len || sig`.
+pub fn sign_pkcs1v15_with_mac(
+    private_key: &RsaPrivateKey,
+    hash_alg: HashAlg,
+    payload: &[u8],
+    mac_key: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    let sig = sign_pkcs1v15(private_key, hash_alg, payload)?;
+    let sig_len = u16::try_from(sig.len()).map_err(|_| {
+        CryptoError::InvalidArgument("signature too long for a 2-byte length prefix")
+    })?;
+
+    let mut blob = Vec::with_capacity(2 + sig.len() + MAC_TAG_LEN);
+    blob.extend_from_slice(&sig_len.to_be_bytes());
+    blob.extend_from_slice(&sig);
+    let tag = frame_mac(mac_key, payload, &blob).finalize().into_bytes();
+    blob.extend_from_slice(&tag);
+    Ok(blob)
+}
+
+/// Verifies a `sign_pkcs1v15_with_mac` blob: the tag first, in constant time,
+/// and only then the RSA signature. A blob whose length prefix does not match
+/// its size is `MalformedToken`, a bad tag `TagMismatch` and a bad signature
+/// `SignatureMismatch`.
+pub fn verify_pkcs1v15_with_mac(
+    public_key: &RsaPublicKey,
+    hash_alg: HashAlg,
+    payload: &[u8],
+    blob: &[u8],
+    mac_key: &[u8],
+) -> Result<(), CryptoError> {
+    let Some((framed_sig, tag)) = blob
+        .len()
+        .checked_sub(MAC_TAG_LEN)
+        .map(|at| blob.split_at(at))
+    else {
+        return Err(CryptoError::MalformedToken(
+            "signature with MAC is truncated",
+        ));
+    };
+    let sig = match framed_sig {
+        [hi, lo, sig @ ..] if usize::from(u16::from_be_bytes([*hi, *lo])) == sig.len() => sig,
+        _ => {
+            return Err(CryptoError::MalformedToken(
+                "signature length prefix does not match the blob",
+            ))
+        }
+    };
+
+    frame_mac(mac_key, payload, framed_sig)
+        .verify_slice(tag)
+        .map_err(|_| CryptoError::TagMismatch)?;
+    verify_pkcs1v15(public_key, hash_alg, payload, sig)
+}
+
+/// Returns how many distinct keys produced a valid PKCS#1 v1.5 signature over
+/// `payload`, pairing each signature with at most one key and each key with at
+/// most one signature. Equal keys are merged first so listing a key twice
+/// cannot double-count its signature.
+pub fn count_distinct_signers(
+    public_keys: &[RsaPublicKey],
+    hash_alg: HashAlg,
+    payload: &[u8],
+    sigs: &[&[u8]],
+) -> usize {
+    let mut unique: Vec<&RsaPublicKey> = Vec::new();
+    for key in public_keys {
+        if !unique.contains(&key) {
+            unique.push(key);
+        }
+    }
+
+    let digest = hash_alg.digest(payload);
+    let valid_for: Vec<Vec<usize>> = unique
+        .iter()
+        .map(|key| {
+            (0..sigs.len())
+                .filter(|&i| verify_pkcs1v15_prehashed(key, hash_alg, &digest, sigs[i]).is_ok())
+                .collect()
+        })
+        .collect();
+
+    // Maximum bipartite matching between keys and signatures (augmenting
+    // paths); the sets are small so the quadratic search is fine.
+    fn augment(
+        key: usize,
+        valid_for: &[Vec<usize>],
+        seen: &mut [bool],
+        sig_owner: &mut [Option<usize>],
+    ) -> bool {
+        for &sig in &valid_for[key] {
+            if seen[sig] {
+                continue;
+            }
+            seen[sig] = true;
+            if sig_owner[sig].is_none_or(|other| augment(other, valid_for, seen, sig_owner)) {
+                sig_owner[sig] = Some(key);
+                return true;
+            }
+        }
+        false
+    }
+
+    let mut sig_owner = vec![None; sigs.len()];
+    (0..unique.len())
+        .filter(|&key| {
+            augment(
+                key,
+                &valid_for,
+                &mut vec![false; sigs.len()],
+                &mut sig_owner,
+            )
+        })
+        .count()
+}
+
+pub fn sign_pkcs1v15_prehashed_with_rng<R: CryptoRngCore>(
+    rng: &mut R,
+    private_key: &RsaPrivateKey,
+    hash_alg: HashAlg,
+    hashed: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    private_key
+        .sign_with_rng(rng, hash_alg.pkcs1v15(), hashed)
+        .map_err(CryptoError::Crypto)
+}
+
+pub fn verify_pkcs1v15(
+    public_key: &RsaPublicKey,
+    hash_alg: HashAlg,
+    payload: &[u8],
+    sig: &[u8],
+) -> Result<(), CryptoError> {
+    verify_pkcs1v15_prehashed(public_key, hash_alg, &hash_alg.digest(payload), sig)
+}
+
+/// A signature whose length is not the modulus size is reported as
+/// `InvalidLength` rather than `SignatureMismatch`: it points at the wrong key
+/// or algorithm being configured, not at a forged signature.
+pub fn verify_pkcs1v15_prehashed(
+    public_key: &RsaPublicKey,
+    hash_alg: HashAlg,
+    hashed: &[u8],
+    sig: &[u8],
+) -> Result<(), CryptoError> {
+    if sig.len() != public_key.size() {
+        return Err(CryptoError::InvalidLength {
+            what: "signature",
+            expected: public_key.size(),
+            actual: sig.len(),
+        });
+    }
+
+    public_key
+        .verify(hash_alg.pkcs1v15(), hashed, sig)
+        .map_err(|_| CryptoError::SignatureMismatch)
+}
+
+/// Hashes `payload` once, checks the digest against `expected` in constant
+/// time and then verifies `sig` over that same digest. A wrong-length
+/// `expected` is `InvalidLength`, a different digest `DigestMismatch`.
+pub fn verify_pkcs1v15_expect_digest(
+    public_key: &RsaPublicKey,
+    hash_alg: HashAlg,
+    payload: &[u8],
+    expected: &[u8],
+    sig: &[u8],
+) -> Result<(), CryptoError> {
+    if expected.len() != hash_alg.output_len() {
+        return Err(CryptoError::InvalidLength {
+            what: "expected digest",
+            expected: hash_alg.output_len(),
+            actual: expected.len(),
+        });
+    }
+
+    let digest = hash_alg.digest(payload);
+    if !bool::from(digest.ct_eq(expected)) {
+        return Err(CryptoError::DigestMismatch);
+    }
+    verify_pkcs1v15_prehashed(public_key, hash_alg, &digest, sig)
+}
+
+// Largest salt EMSA-PSS can fit for this modulus: emLen - hLen - 2. The `rsa`
+// crate does the same check with unchecked arithmetic, so oversized values must
+// be rejected here before they reach it.
+fn max_pss_salt_len(modulus_bits: usize, hash_alg: HashAlg) -> usize {
+    let em_len = (modulus_bits - 1).div_ceil(8);
+    em_len.saturating_sub(hash_alg.output_len() + 2)
+}
+
+fn sign_pss_hashed_with_rng<R: CryptoRngCore>(
+    rng: &mut R,
+    private_key: &RsaPrivateKey,
+    hash_alg: HashAlg,
+    payload: &[u8],
+    salt_len: usize,
+) -> Result<Vec<u8>, CryptoError> {
+    if salt_len > max_pss_salt_len(private_key.n().bits(), hash_alg) {
+        return Err(CryptoError::Crypto(rsa::Error::InvalidPadLen));
+    }
+
+    private_key
+        .sign_with_rng(rng, hash_alg.pss(salt_len), &hash_alg.digest(payload))
+        .map_err(CryptoError::Crypto)
+}
+
+fn verify_pss_hashed(
+    public_key: &RsaPublicKey,
+    hash_alg: HashAlg,
+    payload: &[u8],
+    sig: &[u8],
+    salt_len: usize,
+) -> Result<(), CryptoError> {
+    if salt_len > max_pss_salt_len(public_key.n().bits(), hash_alg) {
+        return Err(CryptoError::SignatureMismatch);
+    }
+
+    public_key
+        .verify(hash_alg.pss(salt_len), &hash_alg.digest(payload), sig)
+        .map_err(|_| CryptoError::SignatureMismatch)
+}
+
+/// Signs `payload` with RSASSA-PSS (SHA-256, MGF1-SHA-256).
+pub fn sign_pss(
+    private_key: &RsaPrivateKey,
+    payload: &[u8],
+    salt_len: usize,
+) -> Result<Vec<u8>, CryptoError> {
+    sign_pss_with_rng(&mut LibRng, private_key, payload, salt_len)
+}
+
+/// Same as `sign_pss` but takes the salt and blinding randomness from `rng`.
+/// With a seeded generator the signature is reproducible.
+pub fn sign_pss_with_rng<R: CryptoRngCore>(
+    rng: &mut R,
+    private_key: &RsaPrivateKey,
+    payload: &[u8],
+    salt_len: usize,
+) -> Result<Vec<u8>, CryptoError> {
+    sign_pss_hashed_with_rng(rng, private_key, HashAlg::Sha256, payload, salt_len)
+}
+
+/// Verifies an RSASSA-PSS (SHA-256, MGF1-SHA-256) signature. A salt length that
+/// differs from the signer's is reported as `SignatureMismatch`.
+pub fn verify_pss(
+    public_key: &RsaPublicKey,
+    payload: &[u8],
+    sig: &[u8],
+    salt_len: usize,
+) -> Result<(), CryptoError> {
+    verify_pss_hashed(public_key, HashAlg::Sha256, payload, sig, salt_len)
+}
+
+// MGF1 from RFC 8017 appendix B.2.1, XORed into `out`.
+fn mgf1_xor(hash_alg: HashAlg, seed: &[u8], out: &mut [u8]) {
+    for (counter, chunk) in (0u32..).zip(out.chunks_mut(hash_alg.output_len())) {
+        let mask = hash_alg.digest_segments([seed, &counter.to_be_bytes()[..]]);
+        chunk.iter_mut().zip(mask).for_each(|(b, m)| *b ^= m);
+    }
+}
+
+/// Verifies an RSASSA-PSS signature whose message digest is `msg_hash` but
+/// whose MGF1 mask uses `mgf_hash`. RFC 8017 allows this, but every common
+/// profile uses one hash for both, and so does the rest of this crate; it
+/// exists only to interoperate with signers configured that way. With equal
+/// hashes it is ordinary PSS. `rsa` ties the two hashes together, so the
+/// mixed case is RSAVP1 and EMSA-PSS-VERIFY (RFC 8017 sections 5.2.2 and
+/// 9.1.2) written out here. Any failure is `SignatureMismatch`.
+pub fn verify_pss_mgf(
+    public_key: &RsaPublicKey,
+    msg_hash: HashAlg,
+    mgf_hash: HashAlg,
+    payload: &[u8],
+    sig: &[u8],
+    salt_len: usize,
+) -> Result<(), CryptoError> {
+    if msg_hash == mgf_hash {
+        return verify_pss_hashed(public_key, msg_hash, payload, sig, salt_len);
+    }
+
+    let s = BigUint::from_bytes_be(sig);
+    if sig.len() != public_key.size() || &s >= public_key.n() {
+        return Err(CryptoError::SignatureMismatch);
+    }
+    let m = s.modpow(public_key.e(), public_key.n()).to_bytes_be();
+
+    let em_bits = public_key.n().bits() - 1;
+    let em_len = em_bits.div_ceil(8);
+    let h_len = msg_hash.output_len();
+    if m.len() > em_len || em_len < h_len + salt_len + 2 {
+        return Err(CryptoError::SignatureMismatch);
+    }
+    let mut em = vec![0u8; em_len];
+    em[em_len - m.len()..].copy_from_slice(&m);
+
+    let top_mask = 0xff_u8 >> (8 * em_len - em_bits);
+    if em[em_len - 1] != 0xbc || em[0] & !top_mask != 0 {
+        return Err(CryptoError::SignatureMismatch);
+    }
+
+    let (db, rest) = em.split_at_mut(em_len - h_len - 1);
+    let h = &rest[..h_len];
+    mgf1_xor(mgf_hash, h, db);
+    db[0] &= top_mask;
+
+    let (padding, salt) = db.split_at(db.len() - salt_len);
+    let (zeros, one) = padding.split_at(padding.len() - 1);
+    let m_hash = msg_hash.digest(payload);
+    let h_prime = msg_hash.digest_segments([&[0u8; 8][..], &m_hash, salt]);
+
+    let valid = zeros.iter().fold(Choice::from(1), |ok, b| ok & b.ct_eq(&0))
+        & one[0].ct_eq(&0x01)
+        & h_prime.ct_eq(h);
+    if bool::from(valid) {
+        Ok(())
+    } else {
+        Err(CryptoError::SignatureMismatch)
+    }
+}
+
+/// Salt length of RSABSSA-SHA384-PSS-Deterministic (RFC 9474 section 5), the
+/// hash output size.
+pub const RSABSSA_SALT_LEN: usize = 48;
+
+// EMSA-PSS-ENCODE (RFC 8017 section 9.1.1) with SHA-384 and MGF1-SHA-384,
+// for an `em_bits`-bit encoding.
+fn emsa_pss_encode_sha384<R: CryptoRngCore>(
+    rng: &mut R,
+    payload: &[u8],
+    em_bits: usize,
+) -> Result<Vec<u8>, CryptoError> {
+    let hash_alg = HashAlg::Sha384;
+    let h_len = hash_alg.output_len();
+    let em_len = em_bits.div_ceil(8);
+    if em_len < h_len + RSABSSA_SALT_LEN + 2 {
+        return Err(CryptoError::Crypto(rsa::Error::InvalidPadLen));
+    }
+
+    let mut salt = [0u8; RSABSSA_SALT_LEN];
+    rng.fill_bytes(&mut salt);
+    let h = hash_alg.digest_segments([&[0u8; 8][..], &hash_alg.digest(payload), &salt]);
+
+    // DB = PS || 0x01 || salt, masked with MGF1(H)
+    let db_len = em_len - h_len - 1;
+    let mut em = vec![0u8; em_len];
+    em[db_len - RSABSSA_SALT_LEN - 1] = 0x01;
+    em[db_len - RSABSSA_SALT_LEN..db_len].copy_from_slice(&salt);
+    mgf1_xor(hash_alg, &h, &mut em[..db_len]);
+    em[0] &= 0xff_u8 >> (8 * em_len - em_bits);
+    em[db_len..em_len - 1].copy_from_slice(&h);
+    em[em_len - 1] = 0xbc;
+    Ok(em)
+}
+
+fn int_to_bytes(x: &BigUint, len: usize) -> Vec<u8> {
+    let bytes = x.to_bytes_be();
+    let mut out = vec![0u8; len];
+    out[len - bytes.len()..].copy_from_slice(&bytes);
+    out
+}
+
+/// Client step of RFC 9474 RSA blind signatures, variant
+/// RSABSSA-SHA384-PSS-Deterministic: the finalized signature is an ordinary
+/// RSASSA-PSS signature (SHA-384, MGF1-SHA-384, 48-byte salt) over `payload`
+/// itself. Returns the `k`-byte blinded message to send to the signer and the
+/// secret blinding state to keep for `rsabssa_finalize`: the inverse of the
+/// blinding factor followed by the SHA-384 of `payload`, `k + 48` bytes.
+///
+/// The deterministic variant skips RFC 9474's random message prefix, which
+/// the RFC recommends only when the application already makes `payload`
+/// unpredictable to the signer, for example by including a random token
+/// nonce.
+pub fn rsabssa_blind<R: CryptoRngCore>(
+    rng: &mut R,
+    public_key: &RsaPublicKey,
+    payload: &[u8],
+) -> Result<(Vec<u8>, Zeroizing<Vec<u8>>), CryptoError> {
+    let n = public_key.n();
+    let k = public_key.size();
+    let m = BigUint::from_bytes_be(&emsa_pss_encode_sha384(rng, payload, n.bits() - 1)?);
+    if m.clone().mod_inverse(n).is_none() {
+        return Err(CryptoError::InvalidArgument(
+            "encoded message is not coprime with the modulus",
+        ));
+    }
+
+    // r uniform in [1, n) and invertible, by rejection sampling
+    let top_mask = 0xff_u8 >> (8 * k - n.bits());
+    let mut candidate = Zeroizing::new(vec![0u8; k]);
+    let (r, inv) = loop {
+        rng.fill_bytes(&mut candidate);
+        candidate[0] &= top_mask;
+        let r = BigUint::from_bytes_be(&candidate);
+        if r.bits() == 0 || &r >= n {
+            continue;
+        }
+        if let Some(inv) = r.clone().mod_inverse(n).and_then(|i| i.to_biguint()) {
+            break (r, inv);
+        }
+    };
+
+    let x = rsa::hazmat::rsa_encrypt(public_key, &r).map_err(CryptoError::Crypto)?;
+    let blinded = int_to_bytes(&((m * x) % n), k);
+
+    let mut state = Zeroizing::new(int_to_bytes(&inv, k));
+    state.extend_from_slice(&HashAlg::Sha384.digest(payload));
+    Ok((blinded, state))
+}
+
+/// Signer step of RFC 9474 RSA blind signatures: the raw RSASP1 of the
+/// `k`-byte blinded message, checked against the public key before it is
+/// returned. The signer learns nothing about the message.
+pub fn rsabssa_blind_sign<R: CryptoRngCore>(
+    rng: &mut R,
+    private_key: &RsaPrivateKey,
+    blinded: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    let k = private_key.size();
+    if blinded.len() != k {
+        return Err(CryptoError::InvalidLength {
+            what: "blinded message",
+            expected: k,
+            actual: blinded.len(),
+        });
+    }
+
+    let m = BigUint::from_bytes_be(blinded);
+    let s = rsa::hazmat::rsa_decrypt_and_check(private_key, Some(rng), &m)
+        .map_err(CryptoError::Crypto)?;
+    Ok(int_to_bytes(&s, k))
+}
+
+/// Client step of RFC 9474 RSA blind signatures: unblinds `blind_sig` with
+/// the state from `rsabssa_blind` and verifies the result as a PSS signature
+/// before returning it. A signature that does not verify, because the
+/// signer used another key or the state belongs to another message, is
+/// `SignatureMismatch`.
+pub fn rsabssa_finalize(
+    public_key: &RsaPublicKey,
+    blind_sig: &[u8],
+    state: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    let k = public_key.size();
+    let h_len = HashAlg::Sha384.output_len();
+    if blind_sig.len() != k {
+        return Err(CryptoError::InvalidLength {
+            what: "blind signature",
+            expected: k,
+            actual: blind_sig.len(),
+        });
+    }
+    if state.len() != k + h_len {
+        return Err(CryptoError::InvalidLength {
+            what: "blinding state",
+            expected: k + h_len,
+            actual: state.len(),
+        });
+    }
+
+    let n = public_key.n();
+    let (inv, msg_hash) = state.split_at(k);
+    let z = BigUint::from_bytes_be(blind_sig);
+    if &z >= n {
+        return Err(CryptoError::SignatureMismatch);
+    }
+    let sig = int_to_bytes(&((z * BigUint::from_bytes_be(inv)) % n), k);
+
+    public_key
+        .verify(HashAlg::Sha384.pss(RSABSSA_SALT_LEN), msg_hash, &sig)
+        .map_err(|_| CryptoError::SignatureMismatch)?;
+    Ok(sig)
+}
+
+/// Signature padding scheme selected by a `SignerConfig`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignaturePadding {
+    Pkcs1v15,
+    /// RSASSA-PSS with MGF1 over the configured hash.
+    Pss,
+}
+
+/// A signing policy that is built once and reused by `Signer` and `Verifier`:
+///
+/// ```
+/// use rustcrypto::api::{HashAlg, SignaturePadding, SignerConfig};
+///
+/// let config = SignerConfig::new().hash_alg(HashAlg::Sha384).padding(SignaturePadding::Pss).pss_salt_len(48);
+/// ```
+///
+/// The default is PKCS#1 v1.5 with SHA-256; `pss_salt_len` (default 32) only
+/// applies to PSS.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SignerConfig {
+    pub hash_alg: HashAlg,
+    pub padding: SignaturePadding,
+    pub pss_salt_len: usize,
+}
+
+/// Verification uses the same policy as signing.
+pub type VerifierConfig = SignerConfig;
+
+impl Default for SignerConfig {
+    fn default() -> Self {
+        SignerConfig {
+            hash_alg: HashAlg::Sha256,
+            padding: SignaturePadding::Pkcs1v15,
+            pss_salt_len: 32,
+        }
+    }
+}
+
+impl SignerConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn hash_alg(mut self, hash_alg: HashAlg) -> Self {
+        self.hash_alg = hash_alg;
+        self
+    }
+
+    pub fn padding(mut self, padding: SignaturePadding) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    pub fn pss_salt_len(mut self, salt_len: usize) -> Self {
+        self.pss_salt_len = salt_len;
+        self
+    }
+
+    // Rejects a PSS salt the key cannot hold up front, so a bad policy fails
+    // when it is pinned rather than on first use.
+    fn check_key(&self, modulus_bits: usize) -> Result<(), CryptoError> {
+        if self.padding == SignaturePadding::Pss
+            && self.pss_salt_len > max_pss_salt_len(modulus_bits, self.hash_alg)
+        {
+            return Err(CryptoError::InvalidArgument(
+                "PSS salt length too large for key",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A private key bound to a `SignerConfig`.
+pub struct Signer {
+    config: SignerConfig,
+    private_key: RsaPrivateKey,
+}
+
+impl Signer {
+    pub fn new(config: SignerConfig, private_key: RsaPrivateKey) -> Result<Self, CryptoError> {
+        config.check_key(private_key.n().bits())?;
+        Ok(Signer {
+            config,
+            private_key,
+        })
+    }
+
+    pub fn config(&self) -> &SignerConfig {
+        &self.config
+    }
+
+    pub fn sign(&self, payload: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        match self.config.padding {
+            SignaturePadding::Pkcs1v15 => {
+                sign_pkcs1v15(&self.private_key, self.config.hash_alg, payload)
+            }
+            SignaturePadding::Pss => sign_pss_hashed_with_rng(
+                &mut LibRng,
+                &self.private_key,
+                self.config.hash_alg,
+                payload,
+                self.config.pss_salt_len,
+            ),
+        }
+    }
+}
+
+/// A public key bound to a `VerifierConfig`.
+pub struct Verifier {
+    config: VerifierConfig,
+    public_key: RsaPublicKey,
+}
+
+impl Verifier {
+    pub fn new(config: VerifierConfig, public_key: RsaPublicKey) -> Result<Self, CryptoError> {
+        config.check_key(public_key.n().bits())?;
+        Ok(Verifier { config, public_key })
+    }
+
+    pub fn config(&self) -> &VerifierConfig {
+        &self.config
+    }
+
+    pub fn verify(&self, payload: &[u8], sig: &[u8]) -> Result<(), CryptoError> {
+        match self.config.padding {
+            SignaturePadding::Pkcs1v15 => {
+                verify_pkcs1v15(&self.public_key, self.config.hash_alg, payload, sig)
+            }
+            SignaturePadding::Pss => verify_pss_hashed(
+                &self.public_key,
+                self.config.hash_alg,
+                payload,
+                sig,
+                self.config.pss_salt_len,
+            ),
+        }
+    }
+}
+
+/// Non-standard compatibility shim: verifies a PKCS#1 v1.5 signature whose
+/// padding carries the bare digest (`0x00 0x01 0xff.. 0x00 || H`) with no
+/// DigestInfo prefix, as produced by some hardware. Such signatures do not
+/// bind the hash algorithm; use `verify_pkcs1v15` for any conforming peer.
+pub fn verify_pkcs1v15_unprefixed(
+    public_key: &RsaPublicKey,
+    hash_alg: HashAlg,
+    payload: &[u8],
+    sig: &[u8],
+) -> Result<(), CryptoError> {
+    if sig.len() != public_key.size() {
+        return Err(CryptoError::InvalidLength {
+            what: "signature",
+            expected: public_key.size(),
+            actual: sig.len(),
+        });
+    }
+    public_key
+        .verify(
+            Pkcs1v15Sign::new_unprefixed(),
+            &hash_alg.digest(payload),
+            sig,
+        )
+        .map_err(|_| CryptoError::SignatureMismatch)
+}
+
+// The DigestInfo prefix for `hash_alg` with the AlgorithmIdentifier
+// parameters omitted rather than NULL: `05 00` dropped and both enclosing
+// SEQUENCE lengths reduced by two.
+fn digest_info_prefix_absent_params(hash_alg: HashAlg) -> Box<[u8]> {
+    let prefix = hash_alg.pkcs1v15().prefix;
+    let oid_end = 4 + 2 + prefix[5] as usize;
+    debug_assert_eq!(&prefix[oid_end..oid_end + 2], &[0x05, 0x00]);
+    let mut absent = [&prefix[..oid_end], &prefix[oid_end + 2..]].concat();
+    absent[1] -= 2;
+    absent[3] -= 2;
+    absent.into_boxed_slice()
+}
+
+/// Verifies a PKCS#1 v1.5 signature, accepting either encoding of the
+/// DigestInfo AlgorithmIdentifier for `hash_alg`: parameters present as an
+/// explicit NULL (`05 00`, what RFC 8017 requires and `verify_pkcs1v15`
+/// accepts) or parameters absent (allowed by RFC 3279 and seen from some
+/// older signers). The digest algorithm must still be `hash_alg` and nothing
+/// else about the padding is relaxed. Any failure is `SignatureMismatch`.
+pub fn verify_pkcs1v15_lenient(
+    public_key: &RsaPublicKey,
+    hash_alg: HashAlg,
+    payload: &[u8],
+    sig: &[u8],
+) -> Result<(), CryptoError> {
+    let hashed = hash_alg.digest(payload);
+    if public_key.verify(hash_alg.pkcs1v15(), &hashed, sig).is_ok() {
+        return Ok(());
+    }
+
+    let absent = Pkcs1v15Sign {
+        hash_len: Some(hashed.len()),
+        prefix: digest_info_prefix_absent_params(hash_alg),
+    };
+    public_key
+        .verify(absent, &hashed, sig)
+        .map_err(|_| CryptoError::SignatureMismatch)
+}
+
+/// The DER RFC 3161 `MessageImprint` of `payload`, the part of a
+/// `TimeStampReq` that carries the hash:
+/// `SEQUENCE { AlgorithmIdentifier { OID, NULL }, OCTET STRING digest }`.
+/// It has the same encoding as a PKCS#1 v1.5 DigestInfo, so it is built the
+/// same way, with NULL parameters as `openssl ts -query` writes them.
+pub fn tsa_message_imprint(hash_alg: HashAlg, payload: &[u8]) -> Vec<u8> {
+    [&hash_alg.pkcs1v15().prefix[..], &hash_alg.digest(payload)].concat()
+}
+
+/// Checks a DER `MessageImprint`, such as the one echoed in a TSA's
+/// `TSTInfo`, against `payload`. Both NULL and absent AlgorithmIdentifier
+/// parameters are accepted. An imprint for `hash_alg` with another digest is
+/// `DigestMismatch`; anything else, including an imprint for a different
+/// hash algorithm, is `MalformedToken`.
+pub fn verify_tsa_message_imprint(
+    hash_alg: HashAlg,
+    payload: &[u8],
+    imprint: &[u8],
+) -> Result<(), CryptoError> {
+    let with_null = hash_alg.pkcs1v15().prefix;
+    let absent = digest_info_prefix_absent_params(hash_alg);
+    let digest = [&with_null[..], &absent[..]]
+        .into_iter()
+        .find_map(|prefix| {
+            imprint
+                .strip_prefix(prefix)
+                .filter(|d| d.len() == hash_alg.output_len())
+        })
+        .ok_or(CryptoError::MalformedToken(
+            "not a MessageImprint for this hash algorithm",
+        ))?;
+
+    if digest == hash_alg.digest(payload).as_slice() {
+        Ok(())
+    } else {
+        Err(CryptoError::DigestMismatch)
+    }
+}
+
+/// Verifies a PKCS#1 v1.5 signature made over the base64url encoding of
+/// `payload` (URL-safe alphabet, no `=` padding) rather than over its bytes.
+pub fn verify_pkcs1v15_b64url_payload(
+    public_key: &RsaPublicKey,
+    hash_alg: HashAlg,
+    payload: &[u8],
+    sig: &[u8],
+) -> Result<(), CryptoError> {
+    verify_pkcs1v15(
+        public_key,
+        hash_alg,
+        URL_SAFE_NO_PAD.encode(payload).as_bytes(),
+        sig,
+    )
+}
+
+// Stand-in for a key that failed to parse, so the failure path still pays for
+// a full-size RSA operation. The modulus is odd but not a product of primes;
+// nothing verifies under it.
+fn ct_dummy_public_key() -> &'static RsaPublicKey {
+    static KEY: OnceLock<RsaPublicKey> = OnceLock::new();
+    KEY.get_or_init(|| {
+        RsaPublicKey::new(
+            BigUint::from_bytes_be(&[0xff; 256]),
+            BigUint::from(65537u32),
+        )
+        .expect("dummy modulus is a valid RSA public key")
+    })
+}
+
+/// Verifies a PKCS#1 v1.5 signature without branching on whether the key
+/// parsed or the signature had the right length. `public_key` is `None` when
+/// parsing failed; a dummy 2048-bit key and a zero signature stand in for the
+/// missing pieces and the RSA operation always runs. Each outcome becomes a
+/// `Choice` and they are combined only at the end.
+pub fn verify_pkcs1v15_ct(
+    public_key: Option<&RsaPublicKey>,
+    hash_alg: HashAlg,
+    payload: &[u8],
+    sig: &[u8],
+) -> Choice {
+    let key_ok = Choice::from(public_key.is_some() as u8);
+    let key = public_key.unwrap_or_else(|| ct_dummy_public_key());
+    let len_ok = (sig.len() as u64).ct_eq(&(key.size() as u64));
+
+    let zeroes;
+    let sig = if sig.len() == key.size() {
+        sig
+    } else {
+        zeroes = vec![0u8; key.size()];
+        &zeroes
+    };
+
+    let sig_ok = Choice::from(
+        key.verify(hash_alg.pkcs1v15(), &hash_alg.digest(payload), sig)
+            .is_ok() as u8,
+    );
+    key_ok & len_ok & sig_ok
+}
+
+/// Debugging aid: undoes the RSA operation on a PKCS#1 v1.5 signature and
+/// returns the digest carried in its padding. Fails with `SignatureMismatch`
+/// if the padding is not a well-formed `hash_alg` DigestInfo, which usually
+/// means the wrong key. A recovered digest proves nothing on its own; use
+/// `verify_pkcs1v15` to verify.
+pub fn pkcs1v15_recover_digest(
+    public_key: &RsaPublicKey,
+    hash_alg: HashAlg,
+    sig: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    let k = public_key.size();
+    if sig.len() != k {
+        return Err(CryptoError::InvalidLength {
+            what: "signature",
+            expected: k,
+            actual: sig.len(),
+        });
+    }
+    let s = BigUint::from_bytes_be(sig);
+    if &s >= public_key.n() {
+        return Err(CryptoError::SignatureMismatch);
+    }
+    let m = s.modpow(public_key.e(), public_key.n()).to_bytes_be();
+    let mut em = vec![0u8; k];
+    em[k - m.len()..].copy_from_slice(&m);
+
+    // EM = 0x00 || 0x01 || PS (0xff...) || 0x00 || DigestInfo prefix || H
+    let prefix = hash_alg.pkcs1v15().prefix;
+    let h_len = hash_alg.output_len();
+    let t_len = prefix.len() + h_len;
+    if k < t_len + 11 {
+        return Err(CryptoError::SignatureMismatch);
+    }
+    let ps_end = k - t_len - 1;
+    let well_formed = em[0] == 0x00
+        && em[1] == 0x01
+        && em[2..ps_end].iter().all(|&b| b == 0xff)
+        && em[ps_end] == 0x00
+        && em[ps_end + 1..k - h_len] == *prefix;
+    if !well_formed {
+        return Err(CryptoError::SignatureMismatch);
+    }
+    Ok(em[k - h_len..].to_vec())
+}
+
+/// Recovers the salt length of an RSASSA-PSS signature whose MGF1 uses the
+/// same hash as `hash_alg`, by running EMSA-PSS-DECODE (RFC 8017 §9.1.2) as
+/// far as the salt. The message hash is never compared, so this only settles
+/// which salt length a peer used; it does not verify anything.
+pub fn pss_detect_salt_len(
+    public_key: &RsaPublicKey,
+    hash_alg: HashAlg,
+    sig: &[u8],
+) -> Result<usize, CryptoError> {
+    if sig.len() != public_key.size() {
+        return Err(CryptoError::InvalidLength {
+            what: "signature",
+            expected: public_key.size(),
+            actual: sig.len(),
+        });
+    }
+    let s = BigUint::from_bytes_be(sig);
+    if &s >= public_key.n() {
+        return Err(CryptoError::SignatureMismatch);
+    }
+    let m = s.modpow(public_key.e(), public_key.n()).to_bytes_be();
+
+    let em_bits = public_key.n().bits() - 1;
+    let em_len = em_bits.div_ceil(8);
+    let h_len = hash_alg.output_len();
+    if m.len() > em_len || em_len < h_len + 2 {
+        return Err(CryptoError::SignatureMismatch);
+    }
+
+    let mut em = vec![0u8; em_len];
+    em[em_len - m.len()..].copy_from_slice(&m);
+    if em[em_len - 1] != 0xbc {
+        return Err(CryptoError::SignatureMismatch);
+    }
+
+    let (db, rest) = em.split_at_mut(em_len - h_len - 1);
+    let h = &rest[..h_len];
+    let top_mask = 0xffu8 >> (8 * em_len - em_bits);
+    if db[0] & !top_mask != 0 {
+        return Err(CryptoError::SignatureMismatch);
+    }
+
+    for (counter, block) in db.chunks_mut(h_len).enumerate() {
+        let mask = hash_alg.digest_segments([h, &(counter as u32).to_be_bytes()[..]]);
+        block
+            .iter_mut()
+            .zip(mask)
+            .for_each(|(b, mask_byte)| *b ^= mask_byte);
+    }
+    db[0] &= top_mask;
+
+    // DB = PS (zeros) || 0x01 || salt
+    match db.iter().position(|&b| b != 0) {
+        Some(i) if db[i] == 0x01 => Ok(db.len() - i - 1),
+        _ => Err(CryptoError::SignatureMismatch),
+    }
+}
+
+/// Parses a PKCS#8 PEM Ed25519 private key.
+pub fn parse_ed25519_private_key_pem(pem: &str) -> Result<SigningKey, CryptoError> {
+    SigningKey::from_pkcs8_pem(pem)
+        .map_err(|e| CryptoError::KeyParse(format!("Ed25519 PKCS#8 PEM: {e}")))
+}
+
+/// Parses a PKCS#8 DER Ed25519 private key.
+pub fn parse_ed25519_private_key_der(der: &[u8]) -> Result<SigningKey, CryptoError> {
+    SigningKey::from_pkcs8_der(der)
+        .map_err(|e| CryptoError::KeyParse(format!("Ed25519 PKCS#8 DER: {e}")))
+}
+
+/// Parses an SPKI PEM Ed25519 public key.
+pub fn parse_ed25519_public_key_pem(pem: &str) -> Result<VerifyingKey, CryptoError> {
+    VerifyingKey::from_public_key_pem(pem)
+        .map_err(|e| CryptoError::KeyParse(format!("Ed25519 SPKI PEM: {e}")))
+}
+
+/// Parses an SPKI DER Ed25519 public key.
+pub fn parse_ed25519_public_key_der(der: &[u8]) -> Result<VerifyingKey, CryptoError> {
+    VerifyingKey::from_public_key_der(der)
+        .map_err(|e| CryptoError::KeyParse(format!("Ed25519 SPKI DER: {e}")))
+}
+
+// Ed25519 hashes the message itself (SHA-512 over R || A || M), so payloads go
+// in untouched. Running them through `HashAlg` first would produce signatures no
+// other Ed25519 implementation accepts.
+
+/// Signs `payload` with Ed25519, returning the 64-byte signature.
+pub fn ed25519_sign(signing_key: &SigningKey, payload: &[u8]) -> Vec<u8> {
+    signing_key.sign(payload).to_bytes().to_vec()
+}
+
+/// Verifies an Ed25519 signature over `payload`. Uses strict verification, so
+/// non-canonical signatures and small-order keys are rejected.
+pub fn ed25519_verify(
+    verifying_key: &VerifyingKey,
+    payload: &[u8],
+    sig: &[u8],
+) -> Result<(), CryptoError> {
+    let sig =
+        ed25519_dalek::Signature::from_slice(sig).map_err(|_| CryptoError::SignatureMismatch)?;
+    verifying_key
+        .verify_strict(payload, &sig)
+        .map_err(|_| CryptoError::SignatureMismatch)
+}
+
+/// Wire encoding of an ECDSA signature.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EcdsaSigFormat {
+    /// Fixed-width `r || s`, as used by JWS (ES256).
+    Raw,
+    /// ASN.1 DER `SEQUENCE { r INTEGER, s INTEGER }`, as used by X.509 and TLS.
+    Der,
+}
+
+impl EcdsaSigFormat {
+    pub(crate) fn from_u32(sig_format: u32) -> Option<EcdsaSigFormat> {
+        match sig_format {
+            ECDSA_SIG_RAW => Some(EcdsaSigFormat::Raw),
+            ECDSA_SIG_DER => Some(EcdsaSigFormat::Der),
+            _ => None,
+        }
+    }
+}
+
+// Longest DER encoding of a P-256 signature: two 33-byte INTEGERs plus headers.
+const P256_DER_SIG_MAX_LEN: usize = 72;
+pub const P256_RAW_SIG_LEN: usize = 64;
+
+/// Parses a PKCS#8 or SEC1 (`BEGIN EC PRIVATE KEY`) PEM P-256 private key.
+pub fn parse_p256_private_key_pem(pem: &str) -> Result<p256::ecdsa::SigningKey, CryptoError> {
+    // Try PKCS8 first, then SEC1
+    match p256::ecdsa::SigningKey::from_pkcs8_pem(pem) {
+        Ok(k) => Ok(k),
+        Err(pkcs8_err) => p256::SecretKey::from_sec1_pem(pem)
+            .map(p256::ecdsa::SigningKey::from)
+            .map_err(|e| {
+                CryptoError::KeyParse(format!("P-256 PKCS#8 PEM: {pkcs8_err}; SEC1 PEM: {e}"))
+            }),
+    }
+}
+
+/// Parses an SPKI PEM P-256 public key.
+pub fn parse_p256_public_key_pem(pem: &str) -> Result<p256::ecdsa::VerifyingKey, CryptoError> {
+    p256::ecdsa::VerifyingKey::from_public_key_pem(pem)
+        .map_err(|e| CryptoError::KeyParse(format!("P-256 SPKI PEM: {e}")))
+}
+
+/// Signs `payload` with ECDSA P-256 / SHA-256 (RFC 6979 deterministic nonces).
+pub fn ecdsa_p256_sign(
+    signing_key: &p256::ecdsa::SigningKey,
+    payload: &[u8],
+    sig_format: EcdsaSigFormat,
+) -> Vec<u8> {
+    let sig: p256::ecdsa::Signature = signing_key.sign(payload);
+    match sig_format {
+        EcdsaSigFormat::Raw => sig.to_bytes().to_vec(),
+        EcdsaSigFormat::Der => sig.to_der().as_bytes().to_vec(),
+    }
+}
+
+/// Verifies an ECDSA P-256 / SHA-256 signature. A raw signature must be exactly
+/// 64 bytes; anything else is rejected before decoding.
+pub fn ecdsa_p256_verify(
+    verifying_key: &p256::ecdsa::VerifyingKey,
+    payload: &[u8],
+    sig: &[u8],
+    sig_format: EcdsaSigFormat,
+) -> Result<(), CryptoError> {
+    let sig = match sig_format {
+        EcdsaSigFormat::Raw if sig.len() != P256_RAW_SIG_LEN => {
+            return Err(CryptoError::SignatureMismatch)
+        }
+        EcdsaSigFormat::Der if sig.len() > P256_DER_SIG_MAX_LEN => {
+            return Err(CryptoError::SignatureMismatch)
+        }
+        EcdsaSigFormat::Raw => p256::ecdsa::Signature::from_slice(sig),
+        EcdsaSigFormat::Der => p256::ecdsa::Signature::from_der(sig),
+    }
+    .map_err(|_| CryptoError::SignatureMismatch)?;
+
+    verifying_key
+        .verify(payload, &sig)
+        .map_err(|_| CryptoError::SignatureMismatch)
+}
+
+/// Converts a DER P-256 ECDSA signature to fixed-width `r || s`. Non-minimal
+/// DER, trailing bytes, and `r` or `s` outside `[1, n)` are rejected.
+pub fn ecdsa_p256_sig_der_to_raw(der: &[u8]) -> Result<[u8; P256_RAW_SIG_LEN], CryptoError> {
+    if der.len() > P256_DER_SIG_MAX_LEN {
+        return Err(CryptoError::InvalidLength {
+            what: "DER ECDSA signature",
+            expected: P256_DER_SIG_MAX_LEN,
+            actual: der.len(),
+        });
+    }
+    let sig = p256::ecdsa::Signature::from_der(der)
+        .map_err(|_| CryptoError::MalformedToken("invalid DER ECDSA signature"))?;
+    Ok(sig.to_bytes().into())
+}
+
+/// Converts a 64-byte `r || s` P-256 ECDSA signature to DER, rejecting `r` or
+/// `s` outside `[1, n)`.
+pub fn ecdsa_p256_sig_raw_to_der(raw: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    if raw.len() != P256_RAW_SIG_LEN {
+        return Err(CryptoError::InvalidLength {
+            what: "raw ECDSA signature",
+            expected: P256_RAW_SIG_LEN,
+            actual: raw.len(),
+        });
+    }
+    let sig = p256::ecdsa::Signature::from_slice(raw)
+        .map_err(|_| CryptoError::MalformedToken("ECDSA signature scalar out of range"))?;
+    Ok(sig.to_der().as_bytes().to_vec())
+}
+
+/// NIST curves accepted by the curve-generic ECDSA functions, each tied to
+/// its JOSE hash: ES256 is P-256 with SHA-256, ES384 P-384 with SHA-384 and
+/// ES512 P-521 with SHA-512.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EcdsaCurve {
+    P256,
+    P384,
+    P521,
+}
+
+impl EcdsaCurve {
+    pub(crate) fn from_u32(curve: u32) -> Option<EcdsaCurve> {
+        match curve {
+            ECDSA_CURVE_P256 => Some(EcdsaCurve::P256),
+            ECDSA_CURVE_P384 => Some(EcdsaCurve::P384),
+            ECDSA_CURVE_P521 => Some(EcdsaCurve::P521),
+            _ => None,
+        }
+    }
+
+    /// The only hash JOSE pairs with this curve.
+    pub fn hash_alg(self) -> HashAlg {
+        match self {
+            EcdsaCurve::P256 => HashAlg::Sha256,
+            EcdsaCurve::P384 => HashAlg::Sha384,
+            EcdsaCurve::P521 => HashAlg::Sha512,
+        }
+    }
+
+    /// Length of a fixed-width `r || s` signature: 64, 96 or 132 bytes.
+    pub fn raw_sig_len(self) -> usize {
+        match self {
+            EcdsaCurve::P256 => P256_RAW_SIG_LEN,
+            EcdsaCurve::P384 => 96,
+            EcdsaCurve::P521 => 132,
+        }
+    }
+
+    // Two INTEGERs of up to half the raw length plus a sign byte, and the
+    // SEQUENCE header (three bytes once the content passes 127 bytes).
+    pub(crate) fn der_sig_max_len(self) -> usize {
+        match self {
+            EcdsaCurve::P256 => P256_DER_SIG_MAX_LEN,
+            EcdsaCurve::P384 => 104,
+            EcdsaCurve::P521 => 141,
+        }
+    }
+}
+
+/// An ECDSA private key on one of the `EcdsaCurve` curves.
+pub enum EcdsaSigningKey {
+    P256(p256::ecdsa::SigningKey),
+    P384(p384::ecdsa::SigningKey),
+    P521(p521::ecdsa::SigningKey),
+}
+
+/// An ECDSA public key on one of the `EcdsaCurve` curves.
+pub enum EcdsaVerifyingKey {
+    P256(p256::ecdsa::VerifyingKey),
+    P384(p384::ecdsa::VerifyingKey),
+    P521(p521::ecdsa::VerifyingKey),
+}
+
+/// Parses a PKCS#8 or SEC1 PEM private key on `curve`. A key on another curve
+/// is `KeyParse`.
+pub fn parse_ecdsa_private_key_pem(
+    curve: EcdsaCurve,
+    pem: &str,
+) -> Result<EcdsaSigningKey, CryptoError> {
+    match curve {
+        EcdsaCurve::P256 => parse_p256_private_key_pem(pem).map(EcdsaSigningKey::P256),
+        EcdsaCurve::P384 => match p384::SecretKey::from_pkcs8_pem(pem) {
+            Ok(k) => Ok(EcdsaSigningKey::P384(k.into())),
+            Err(pkcs8_err) => p384::SecretKey::from_sec1_pem(pem)
+                .map(|k| EcdsaSigningKey::P384(k.into()))
+                .map_err(|e| {
+                    CryptoError::KeyParse(format!("P-384 PKCS#8 PEM: {pkcs8_err}; SEC1 PEM: {e}"))
+                }),
+        },
+        EcdsaCurve::P521 => {
+            let secret = match p521::SecretKey::from_pkcs8_pem(pem) {
+                Ok(k) => k,
+                Err(pkcs8_err) => p521::SecretKey::from_sec1_pem(pem).map_err(|e| {
+                    CryptoError::KeyParse(format!("P-521 PKCS#8 PEM: {pkcs8_err}; SEC1 PEM: {e}"))
+                })?,
+            };
+            p521::ecdsa::SigningKey::from_bytes(&secret.to_bytes())
+                .map(EcdsaSigningKey::P521)
+                .map_err(|e| CryptoError::KeyParse(format!("P-521 private key: {e}")))
+        }
+    }
+}
+
+/// Parses an SPKI PEM public key on `curve`.
+pub fn parse_ecdsa_public_key_pem(
+    curve: EcdsaCurve,
+    pem: &str,
+) -> Result<EcdsaVerifyingKey, CryptoError> {
+    match curve {
+        EcdsaCurve::P256 => parse_p256_public_key_pem(pem).map(EcdsaVerifyingKey::P256),
+        EcdsaCurve::P384 => p384::ecdsa::VerifyingKey::from_public_key_pem(pem)
+            .map(EcdsaVerifyingKey::P384)
+            .map_err(|e| CryptoError::KeyParse(format!("P-384 SPKI PEM: {e}"))),
+        EcdsaCurve::P521 => {
+            let public = p521::PublicKey::from_public_key_pem(pem)
+                .map_err(|e| CryptoError::KeyParse(format!("P-521 SPKI PEM: {e}")))?;
+            p521::ecdsa::VerifyingKey::from_affine(*public.as_affine())
+                .map(EcdsaVerifyingKey::P521)
+                .map_err(|e| CryptoError::KeyParse(format!("P-521 public key: {e}")))
+        }
+    }
+}
+
+/// Signs `payload` with ECDSA over the key's curve and its JOSE hash. P-256
+/// and P-384 nonces are RFC 6979 deterministic; `p521` does not implement
+/// RFC 6979, so P-521 nonces are drawn from the library RNG and its signatures
+/// differ on every call. Raw signatures are `EcdsaCurve::raw_sig_len` bytes.
+pub fn ecdsa_sign(
+    signing_key: &EcdsaSigningKey,
+    payload: &[u8],
+    sig_format: EcdsaSigFormat,
+) -> Vec<u8> {
+    match signing_key {
+        EcdsaSigningKey::P256(k) => ecdsa_p256_sign(k, payload, sig_format),
+        EcdsaSigningKey::P384(k) => {
+            let sig: p384::ecdsa::Signature = k.sign(payload);
+            match sig_format {
+                EcdsaSigFormat::Raw => sig.to_bytes().to_vec(),
+                EcdsaSigFormat::Der => sig.to_der().as_bytes().to_vec(),
+            }
+        }
+        EcdsaSigningKey::P521(k) => {
+            let sig: p521::ecdsa::Signature =
+                p521::ecdsa::signature::RandomizedSigner::sign_with_rng(k, &mut LibRng, payload);
+            match sig_format {
+                EcdsaSigFormat::Raw => sig.to_bytes().to_vec(),
+                EcdsaSigFormat::Der => sig.to_der().as_bytes().to_vec(),
+            }
+        }
+    }
+}
+
+/// Verifies an ECDSA signature over the key's curve and its JOSE hash. A raw
+/// signature of the wrong width, or DER longer than the curve allows, is
+/// rejected before decoding.
+pub fn ecdsa_verify(
+    verifying_key: &EcdsaVerifyingKey,
+    payload: &[u8],
+    sig: &[u8],
+    sig_format: EcdsaSigFormat,
+) -> Result<(), CryptoError> {
+    let check_len = |curve: EcdsaCurve| match sig_format {
+        EcdsaSigFormat::Raw if sig.len() != curve.raw_sig_len() => {
+            Err(CryptoError::SignatureMismatch)
+        }
+        EcdsaSigFormat::Der if sig.len() > curve.der_sig_max_len() => {
+            Err(CryptoError::SignatureMismatch)
+        }
+        _ => Ok(()),
+    };
+
+    match verifying_key {
+        EcdsaVerifyingKey::P256(k) => ecdsa_p256_verify(k, payload, sig, sig_format),
+        EcdsaVerifyingKey::P384(k) => {
+            check_len(EcdsaCurve::P384)?;
+            let sig = match sig_format {
+                EcdsaSigFormat::Raw => p384::ecdsa::Signature::from_slice(sig),
+                EcdsaSigFormat::Der => p384::ecdsa::Signature::from_der(sig),
+            }
+            .map_err(|_| CryptoError::SignatureMismatch)?;
+            k.verify(payload, &sig)
+                .map_err(|_| CryptoError::SignatureMismatch)
+        }
+        EcdsaVerifyingKey::P521(k) => {
+            check_len(EcdsaCurve::P521)?;
+            let sig = match sig_format {
+                EcdsaSigFormat::Raw => p521::ecdsa::Signature::from_slice(sig),
+                EcdsaSigFormat::Der => p521::ecdsa::Signature::from_der(sig),
+            }
+            .map_err(|_| CryptoError::SignatureMismatch)?;
+            k.verify(payload, &sig)
+                .map_err(|_| CryptoError::SignatureMismatch)
+        }
+    }
+}
+
+/// Signature schemes, for sizing signature buffers and policy checks.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SignatureScheme {
+    RsaPkcs1v15,
+    RsaPss,
+    Ecdsa,
+    Ed25519,
+}
+
+impl SignatureScheme {
+    pub(crate) fn from_i32(scheme: i32) -> Option<SignatureScheme> {
+        match scheme {
+            SIG_SCHEME_RSA_PKCS1V15 => Some(SignatureScheme::RsaPkcs1v15),
+            SIG_SCHEME_RSA_PSS => Some(SignatureScheme::RsaPss),
+            SIG_SCHEME_ECDSA => Some(SignatureScheme::Ecdsa),
+            SIG_SCHEME_ED25519 => Some(SignatureScheme::Ed25519),
+            _ => None,
+        }
+    }
+}
+
+/// Length in bytes of a signature made with `scheme`, or `None` for a
+/// combination that cannot be signed:
+///
+/// - RSA (either padding): the modulus size, `ceil(bits / 8)`, for
+///   `curve_or_bits` between 1 and `RSA_MAX_MODULUS_BITS`. `der` must be false.
+/// - ECDSA: `curve_or_bits` is an `ECDSA_CURVE_*` value. Fixed-width `r || s`
+///   is twice the field size (64, 96 or 132); DER is the largest encoding
+///   (72, 104 or 141), and actual signatures are usually a few bytes shorter.
+/// - Ed25519: always 64; `curve_or_bits` is ignored and `der` must be false.
+pub fn expected_signature_len(
+    scheme: SignatureScheme,
+    curve_or_bits: u32,
+    der: bool,
+) -> Option<usize> {
+    match scheme {
+        SignatureScheme::RsaPkcs1v15 | SignatureScheme::RsaPss if !der => {
+            let bits = curve_or_bits as usize;
+            (1..=RSA_MAX_MODULUS_BITS)
+                .contains(&bits)
+                .then(|| bits.div_ceil(8))
+        }
+        SignatureScheme::Ecdsa => {
+            let curve = EcdsaCurve::from_u32(curve_or_bits)?;
+            Some(if der {
+                curve.der_sig_max_len()
+            } else {
+                curve.raw_sig_len()
+            })
+        }
+        SignatureScheme::Ed25519 if !der => Some(ed25519_dalek::SIGNATURE_LENGTH),
+        _ => None,
+    }
+}
+
+/// Verdict of `deprecation_status`, ordered from best to worst.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DeprecationStatus {
+    Ok,
+    Deprecated,
+    Forbidden,
+}
+
+// The deprecation policy. These are the only thresholds; change them here.
+/// RSA moduli below this many bits are `Forbidden`.
+pub const RSA_FORBIDDEN_BELOW_BITS: u32 = 2048;
+/// RSA moduli below this many bits (and at least `RSA_FORBIDDEN_BELOW_BITS`)
+/// are `Deprecated`.
+pub const RSA_DEPRECATED_BELOW_BITS: u32 = 3072;
+
+fn hash_deprecation_status(hash_alg: HashAlg) -> DeprecationStatus {
+    match hash_alg {
+        HashAlg::Sha1 => DeprecationStatus::Deprecated,
+        HashAlg::Sha256
+        | HashAlg::Sha384
+        | HashAlg::Sha512
+        | HashAlg::Sha3_256
+        | HashAlg::Sha3_384
+        | HashAlg::Sha3_512 => DeprecationStatus::Ok,
+    }
+}
+
+/// Classifies a scheme under the policy above, so every service warns about
+/// the same things. For RSA the worse of the key size and the hash wins:
+/// under `RSA_FORBIDDEN_BELOW_BITS` is forbidden, under
+/// `RSA_DEPRECATED_BELOW_BITS` is deprecated, and SHA-1 is deprecated. ECDSA
+/// (`key_bits` 256, 384 or 521, hashed with the curve's own SHA-2) and
+/// Ed25519 (`key_bits` ignored) are `Ok`; `hash_alg` does not apply to them.
+/// `None` for an ECDSA size that is not a supported curve.
+pub fn deprecation_status(
+    scheme: SignatureScheme,
+    hash_alg: HashAlg,
+    key_bits: u32,
+) -> Option<DeprecationStatus> {
+    match scheme {
+        SignatureScheme::RsaPkcs1v15 | SignatureScheme::RsaPss => {
+            let key_status = if key_bits < RSA_FORBIDDEN_BELOW_BITS {
+                DeprecationStatus::Forbidden
+            } else if key_bits < RSA_DEPRECATED_BELOW_BITS {
+                DeprecationStatus::Deprecated
+            } else {
+                DeprecationStatus::Ok
+            };
+            Some(key_status.max(hash_deprecation_status(hash_alg)))
+        }
+        SignatureScheme::Ecdsa => {
+            matches!(key_bits, 256 | 384 | 521).then_some(DeprecationStatus::Ok)
+        }
+        SignatureScheme::Ed25519 => Some(DeprecationStatus::Ok),
+    }
+}
+
+pub const SECP256K1_DIGEST_LEN: usize = 32;
+pub const SECP256K1_SIG_LEN: usize = 64;
+
+/// Parses a SEC1 secp256k1 public key, compressed (33 bytes) or uncompressed
+/// (65 bytes).
+pub fn parse_secp256k1_public_key(sec1: &[u8]) -> Result<k256::ecdsa::VerifyingKey, CryptoError> {
+    // `from_sec1_bytes` also takes the identity and "compact" encodings, which
+    // no peer of ours sends.
+    match (sec1.len(), sec1.first()) {
+        (33, Some(0x02 | 0x03)) | (65, Some(0x04)) => {}
+        _ => {
+            return Err(CryptoError::KeyParse(
+                "secp256k1 public key must be 33-byte compressed or 65-byte uncompressed SEC1"
+                    .into(),
+            ))
+        }
+    }
+    k256::ecdsa::VerifyingKey::from_sec1_bytes(sec1)
+        .map_err(|_| CryptoError::KeyParse("secp256k1 public key is not a curve point".into()))
+}
+
+/// Verifies a 64-byte `r || s` secp256k1 ECDSA signature over a 32-byte digest
+/// the caller has already computed (e.g. Keccak-256). As in Ethereum, high-S
+/// signatures are rejected.
+pub fn secp256k1_verify_prehashed(
+    verifying_key: &k256::ecdsa::VerifyingKey,
+    digest: &[u8],
+    sig: &[u8],
+) -> Result<(), CryptoError> {
+    if digest.len() != SECP256K1_DIGEST_LEN {
+        return Err(CryptoError::InvalidLength {
+            what: "secp256k1 digest",
+            expected: SECP256K1_DIGEST_LEN,
+            actual: digest.len(),
+        });
+    }
+    if sig.len() != SECP256K1_SIG_LEN {
+        return Err(CryptoError::InvalidLength {
+            what: "secp256k1 signature",
+            expected: SECP256K1_SIG_LEN,
+            actual: sig.len(),
+        });
+    }
+    let sig = k256::ecdsa::Signature::from_slice(sig)
+        .map_err(|_| CryptoError::MalformedToken("ECDSA signature scalar out of range"))?;
+    verifying_key
+        .verify_prehash(digest, &sig)
+        .map_err(|_| CryptoError::SignatureMismatch)
+}
+
+/// Extracts the RSA public key from a DER X.509 certificate's
+/// SubjectPublicKeyInfo. Neither the certificate's own signature nor its chain
+/// is checked.
+pub fn parse_certificate_public_key_der(cert_der: &[u8]) -> Result<RsaPublicKey, CryptoError> {
+    certificate_public_key(&parse_certificate_der(cert_der)?)
+}
+
+fn parse_certificate_der(cert_der: &[u8]) -> Result<Certificate, CryptoError> {
+    Certificate::from_der(cert_der).map_err(|e| CryptoError::KeyParse(format!("X.509 DER: {e}")))
+}
+
+fn certificate_public_key(cert: &Certificate) -> Result<RsaPublicKey, CryptoError> {
+    let spki_der = cert
+        .tbs_certificate
+        .subject_public_key_info
+        .to_der()
+        .map_err(|e| CryptoError::KeyParse(format!("X.509 SPKI: {e}")))?;
+    parse_public_key_der(&spki_der)
+}
+
+/// Returns a DER X.509 certificate's `notAfter` as seconds since the Unix epoch.
+pub fn certificate_not_after(cert_der: &[u8]) -> Result<i64, CryptoError> {
+    let cert = parse_certificate_der(cert_der)?;
+    Ok(cert
+        .tbs_certificate
+        .validity
+        .not_after
+        .to_unix_duration()
+        .as_secs() as i64)
+}
+
+/// Returns the subject Common Name of a DER X.509 certificate, or `None` if
+/// the subject has none. With several CN attributes the last, most specific
+/// one wins, as RFC 6125 section 6.4.4 describes.
+pub fn certificate_subject_cn(cert_der: &[u8]) -> Result<Option<String>, CryptoError> {
+    let cert = parse_certificate_der(cert_der)?;
+    let cn = cert
+        .tbs_certificate
+        .subject
+        .0
+        .iter()
+        .flat_map(|rdn| rdn.0.iter())
+        .rfind(|atv| atv.oid == COMMON_NAME);
+    cn.map(|atv| directory_string(&atv.value)).transpose()
+}
+
+fn directory_string(value: &Any) -> Result<String, CryptoError> {
+    let decoded = match value.tag() {
+        Tag::Utf8String => value
+            .decode_as::<Utf8StringRef<'_>>()
+            .map(|s| s.to_string()),
+        Tag::PrintableString => value
+            .decode_as::<PrintableStringRef<'_>>()
+            .map(|s| s.to_string()),
+        Tag::TeletexString => value
+            .decode_as::<TeletexStringRef<'_>>()
+            .map(|s| s.to_string()),
+        Tag::Ia5String => value.decode_as::<Ia5StringRef<'_>>().map(|s| s.to_string()),
+        Tag::BmpString => value.decode_as::<BmpString>().map(|s| s.to_string()),
+        tag => {
+            return Err(CryptoError::KeyParse(format!(
+                "X.509 name attribute has unsupported string type {tag}"
+            )))
+        }
+    };
+    decoded.map_err(|e| CryptoError::KeyParse(format!("X.509 name attribute: {e}")))
+}
+
+/// Returns the dNSName entries of a DER X.509 certificate's subjectAltName
+/// extension, in certificate order; empty if it has no such extension. Other
+/// name types are skipped. A name containing a control character is
+/// `KeyParse`, so a newline-joined list always has one name per line.
+pub fn certificate_san_dns_names(cert_der: &[u8]) -> Result<Vec<String>, CryptoError> {
+    let cert = parse_certificate_der(cert_der)?;
+    let san = match cert.tbs_certificate.get::<SubjectAltName>() {
+        Ok(Some((_, san))) => san,
+        Ok(None) => return Ok(Vec::new()),
+        Err(e) => return Err(CryptoError::KeyParse(format!("X.509 subjectAltName: {e}"))),
+    };
+
+    san.0
+        .iter()
+        .filter_map(|name| match name {
+            GeneralName::DnsName(dns) => Some(dns.to_string()),
+            _ => None,
+        })
+        .map(|dns| {
+            if dns.chars().any(|c| c.is_ascii_control()) {
+                return Err(CryptoError::KeyParse(
+                    "X.509 subjectAltName dNSName contains a control character".into(),
+                ));
+            }
+            Ok(dns)
+        })
+        .collect()
+}
+
+/// Validates a chain of DER certificates from `leaf` through `intermediates`
+/// (in any order) to the pinned `root`, at `now` seconds since the Unix epoch.
+///
+/// Checked: issuer/subject name linkage, each certificate's RSA PKCS#1 v1.5
+/// signature (SHA-1/256/384/512) under its issuer's key, every certificate's
+/// notBefore/notAfter, and the basicConstraints CA flag on every issuer.
+///
+/// Not checked: revocation (see `check_crl`), key usage and extended key
+/// usage, path length and name constraints, policies, unknown critical
+/// extensions, or the root's own signature (the root is trusted as given).
+pub fn verify_cert_chain(
+    leaf: &[u8],
+    intermediates: &[&[u8]],
+    root: &[u8],
+    now: u64,
+) -> Result<(), CryptoError> {
+    let leaf = parse_certificate_der(leaf)?;
+    let root = parse_certificate_der(root)?;
+    let mut pool = intermediates
+        .iter()
+        .map(|der| parse_certificate_der(der))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    check_validity(&leaf, now)?;
+
+    // Each intermediate can be used once, so the walk always terminates.
+    let mut current = leaf;
+    loop {
+        if current.tbs_certificate.issuer == root.tbs_certificate.subject {
+            return check_issued_by(&current, &root, now);
+        }
+        let next = pool
+            .iter()
+            .position(|c| c.tbs_certificate.subject == current.tbs_certificate.issuer)
+            .ok_or(CryptoError::UntrustedRoot)?;
+        let issuer = pool.swap_remove(next);
+        check_issued_by(&current, &issuer, now)?;
+        current = issuer;
+    }
+}
+
+fn check_validity(cert: &Certificate, now: u64) -> Result<(), CryptoError> {
+    let validity = &cert.tbs_certificate.validity;
+    if now < validity.not_before.to_unix_duration().as_secs()
+        || now > validity.not_after.to_unix_duration().as_secs()
+    {
+        return Err(CryptoError::CertExpired);
+    }
+    Ok(())
+}
+
+fn check_issued_by(cert: &Certificate, issuer: &Certificate, now: u64) -> Result<(), CryptoError> {
+    check_validity(issuer, now)?;
+
+    match issuer.tbs_certificate.get::<BasicConstraints>() {
+        Ok(Some((_, constraints))) if constraints.ca => {}
+        _ => return Err(CryptoError::CertNotCa),
+    }
+
+    let tbs = cert
+        .tbs_certificate
+        .to_der()
+        .map_err(|e| CryptoError::KeyParse(format!("X.509 TBS: {e}")))?;
+    verify_x509_signature(
+        &certificate_public_key(issuer)?,
+        &cert.signature_algorithm,
+        &tbs,
+        cert.signature.as_bytes(),
+    )
+}
+
+// RSA PKCS#1 v1.5 signature over a certificate's or CRL's TBS bytes.
+fn verify_x509_signature(
+    public_key: &RsaPublicKey,
+    algorithm: &AlgorithmIdentifierOwned,
+    tbs: &[u8],
+    sig: Option<&[u8]>,
+) -> Result<(), CryptoError> {
+    let hash_alg = match algorithm.oid {
+        SHA_256_WITH_RSA_ENCRYPTION => HashAlg::Sha256,
+        SHA_384_WITH_RSA_ENCRYPTION => HashAlg::Sha384,
+        SHA_512_WITH_RSA_ENCRYPTION => HashAlg::Sha512,
+        SHA_1_WITH_RSA_ENCRYPTION => HashAlg::Sha1,
+        oid => {
+            return Err(CryptoError::UnsupportedAlgId(format!(
+                "X.509 signature algorithm {oid}"
+            )))
+        }
+    };
+
+    let sig = sig.ok_or(CryptoError::SignatureMismatch)?;
+    match verify_pkcs1v15(public_key, hash_alg, tbs, sig) {
+        Err(CryptoError::InvalidLength { .. }) => Err(CryptoError::SignatureMismatch),
+        result => result,
+    }
+}
+
+/// Checks `cert` against a DER CRL. The CRL must be signed (RSA PKCS#1 v1.5)
+/// by `issuer_key`, else `SignatureMismatch`, and its issuer name must be the
+/// certificate's issuer, else `InvalidArgument`. A listed serial number is
+/// `CertRevoked`. The CRL's thisUpdate/nextUpdate, delta CRLs and entry
+/// extensions such as removeFromCRL are not checked.
+pub fn check_crl(
+    cert_der: &[u8],
+    crl_der: &[u8],
+    issuer_key: &RsaPublicKey,
+) -> Result<(), CryptoError> {
+    let cert = parse_certificate_der(cert_der)?;
+    let crl = CertificateList::from_der(crl_der)
+        .map_err(|e| CryptoError::KeyParse(format!("X.509 CRL DER: {e}")))?;
+
+    let tbs = crl
+        .tbs_cert_list
+        .to_der()
+        .map_err(|e| CryptoError::KeyParse(format!("X.509 CRL TBS: {e}")))?;
+    verify_x509_signature(
+        issuer_key,
+        &crl.signature_algorithm,
+        &tbs,
+        crl.signature.as_bytes(),
+    )?;
+
+    if crl.tbs_cert_list.issuer != cert.tbs_certificate.issuer {
+        return Err(CryptoError::InvalidArgument(
+            "CRL issuer does not match the certificate issuer",
+        ));
+    }
+
+    let serial = &cert.tbs_certificate.serial_number;
+    let revoked = crl
+        .tbs_cert_list
+        .revoked_certificates
+        .iter()
+        .flatten()
+        .any(|entry| entry.serial_number == *serial);
+    if revoked {
+        return Err(CryptoError::CertRevoked);
+    }
+    Ok(())
+}
+
+pub const AES_256_GCM_KEY_LEN: usize = 32;
+pub const AES_256_GCM_NONCE_LEN: usize = 12;
+
+fn aes_256_gcm(key: &[u8], nonce: &[u8]) -> Result<Aes256Gcm, CryptoError> {
+    if key.len() != AES_256_GCM_KEY_LEN {
+        return Err(CryptoError::InvalidLength {
+            what: "AES-256-GCM key",
+            expected: AES_256_GCM_KEY_LEN,
+            actual: key.len(),
+        });
+    }
+    if nonce.len() != AES_256_GCM_NONCE_LEN {
+        return Err(CryptoError::InvalidLength {
+            what: "AES-256-GCM nonce",
+            expected: AES_256_GCM_NONCE_LEN,
+            actual: nonce.len(),
+        });
+    }
+    Ok(Aes256Gcm::new_from_slice(key).expect("key length checked above"))
+}
+
+/// Encrypts with AES-256-GCM, returning the ciphertext with the 16-byte tag
+/// appended. A nonce must never be reused with the same key.
+pub fn aes_gcm_encrypt(
+    key: &[u8],
+    nonce: &[u8],
+    plaintext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    let cipher = aes_256_gcm(key, nonce)?;
+    cipher
+        .encrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: plaintext,
+                aad,
+            },
+        )
+        .map_err(|_| CryptoError::Aead("plaintext too long to encrypt"))
+}
+
+/// Decrypts AES-256-GCM `ciphertext || tag`. Any modification of the
+/// ciphertext, tag or AAD is reported as `TagMismatch`.
+pub fn aes_gcm_decrypt(
+    key: &[u8],
+    nonce: &[u8],
+    ciphertext: &[u8],
+    aad: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    let cipher = aes_256_gcm(key, nonce)?;
+    cipher
+        .decrypt(
+            Nonce::from_slice(nonce),
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| CryptoError::TagMismatch)
+}
+
+pub const AES_GCM_TAG_LEN: usize = 16;
+pub const AES_GCM_STREAM_NONCE_LEN: usize = 7;
+pub const AES_GCM_STREAM_SEGMENT_LEN: usize = 64 * 1024;
+
+// Streaming AES-256-GCM uses the STREAM construction (Hoang et al.) with a
+// 32-bit big-endian segment counter: segment `i` is sealed under the nonce
+// `prefix || i || last_flag`, so no nonce repeats within a stream and
+// truncating, reordering or extending segments fails authentication. Every
+// segment but the last carries `AES_GCM_STREAM_SEGMENT_LEN` bytes of plaintext
+// and its own tag; the last segment's tag is the one handed out separately.
+// The caller must still never reuse a (key, prefix) pair across streams.
+
+fn aes_gcm_stream_init(key: &[u8], nonce: &[u8]) -> Result<Aes256Gcm, CryptoError> {
+    if key.len() != AES_256_GCM_KEY_LEN {
+        return Err(CryptoError::InvalidLength {
+            what: "AES-256-GCM key",
+            expected: AES_256_GCM_KEY_LEN,
+            actual: key.len(),
+        });
+    }
+    if nonce.len() != AES_GCM_STREAM_NONCE_LEN {
+        return Err(CryptoError::InvalidLength {
+            what: "AES-256-GCM stream nonce",
+            expected: AES_GCM_STREAM_NONCE_LEN,
+            actual: nonce.len(),
+        });
+    }
+    Ok(Aes256Gcm::new_from_slice(key).expect("key length checked above"))
+}
+
+/// Incremental AES-256-GCM encryption, see the STREAM notes above. `update`
+/// returns ciphertext as whole segments become available; `finalize` returns
+/// the remaining ciphertext and the final 16-byte tag.
+pub struct AesGcmStreamEncryptor {
+    stream: Option<EncryptorBE32<Aes256Gcm>>,
+    aad: Vec<u8>,
+    pending: Zeroizing<Vec<u8>>,
+}
+
+impl AesGcmStreamEncryptor {
+    /// `nonce` is the 7-byte stream prefix. `aad` is authenticated with
+    /// every segment.
+    pub fn new(key: &[u8], nonce: &[u8], aad: &[u8]) -> Result<Self, CryptoError> {
+        let cipher = aes_gcm_stream_init(key, nonce)?;
+        let stream = EncryptorBE32::from_aead(cipher, nonce.into());
+        Ok(AesGcmStreamEncryptor {
+            stream: Some(stream),
+            aad: aad.to_vec(),
+            pending: Zeroizing::new(Vec::new()),
+        })
+    }
+
+    pub fn update(&mut self, plaintext: &[u8]) -> Result<Vec<u8>, CryptoError> {
+        let stream = self.stream.as_mut().ok_or(CryptoError::InvalidArgument(
+            "AES-GCM stream already finalized",
+        ))?;
+        self.pending.extend_from_slice(plaintext);
+
+        // Hold back a full segment until more input arrives so the last
+        // segment is never empty unless the whole stream is.
+        let mut out = Vec::new();
+        let mut start = 0;
+        while self.pending.len() - start > AES_GCM_STREAM_SEGMENT_LEN {
+            let segment = &self.pending[start..start + AES_GCM_STREAM_SEGMENT_LEN];
+            let sealed = stream
+                .encrypt_next(Payload {
+                    msg: segment,
+                    aad: &self.aad,
+                })
+                .map_err(|_| CryptoError::Aead("stream too long to encrypt"))?;
+            out.extend_from_slice(&sealed);
+            start += AES_GCM_STREAM_SEGMENT_LEN;
+        }
+        self.pending.drain(..start);
+        Ok(out)
+    }
+
+    pub fn finalize(&mut self) -> Result<(Vec<u8>, [u8; AES_GCM_TAG_LEN]), CryptoError> {
+        let stream = self.stream.take().ok_or(CryptoError::InvalidArgument(
+            "AES-GCM stream already finalized",
+        ))?;
+        let mut sealed = stream
+            .encrypt_last(Payload {
+                msg: &self.pending,
+                aad: &self.aad,
+            })
+            .map_err(|_| CryptoError::Aead("stream too long to encrypt"))?;
+        self.pending.clear();
+
+        let tag_start = sealed.len() - AES_GCM_TAG_LEN;
+        let tag = sealed[tag_start..]
+            .try_into()
+            .expect("sealed segment ends with a tag");
+        sealed.truncate(tag_start);
+        Ok((sealed, tag))
+    }
+}
+
+/// Decrypting counterpart of `AesGcmStreamEncryptor`. Plaintext from `update`
+/// is authentic segment by segment, but the stream as a whole is only
+/// authenticated once `finalize` succeeds; until then it may be truncated.
+pub struct AesGcmStreamDecryptor {
+    stream: Option<DecryptorBE32<Aes256Gcm>>,
+    aad: Vec<u8>,
+    pending: Vec<u8>,
+}
+
+impl AesGcmStreamDecryptor {
+    pub fn new(key: &[u8], nonce: &[u8], aad: &[u8]) -> Result<Self, CryptoError> {
+        let cipher = aes_gcm_stream_init(key, nonce)?;
+        let stream = DecryptorBE32::from_aead(cipher, nonce.into());
+        Ok(AesGcmStreamDecryptor {
+            stream: Some(stream),
+            aad: aad.to_vec(),
+            pending: Vec::new(),
+        })
+    }
+
+    pub fn update(&mut self, ciphertext: &[u8]) -> Result<Zeroizing<Vec<u8>>, CryptoError> {
+        let stream = self.stream.as_mut().ok_or(CryptoError::InvalidArgument(
+            "AES-GCM stream already finalized",
+        ))?;
+        self.pending.extend_from_slice(ciphertext);
+
+        // The last segment is at most one segment of ciphertext with its tag
+        // passed separately, so every full sealed segment here is a middle one.
+        const SEALED_LEN: usize = AES_GCM_STREAM_SEGMENT_LEN + AES_GCM_TAG_LEN;
+        let mut out = Zeroizing::new(Vec::new());
+        let mut start = 0;
+        while self.pending.len() - start >= SEALED_LEN {
+            let opened = Zeroizing::new(
+                stream
+                    .decrypt_next(Payload {
+                        msg: &self.pending[start..start + SEALED_LEN],
+                        aad: &self.aad,
+                    })
+                    .map_err(|_| CryptoError::TagMismatch)?,
+            );
+            out.extend_from_slice(&opened);
+            start += SEALED_LEN;
+        }
+        self.pending.drain(..start);
+        Ok(out)
+    }
+
+    /// Checks the final segment against `tag` and returns its plaintext.
+    pub fn finalize(&mut self, tag: &[u8]) -> Result<Zeroizing<Vec<u8>>, CryptoError> {
+        if tag.len() != AES_GCM_TAG_LEN {
+            return Err(CryptoError::InvalidLength {
+                what: "AES-GCM tag",
+                expected: AES_GCM_TAG_LEN,
+                actual: tag.len(),
+            });
+        }
+        let stream = self.stream.take().ok_or(CryptoError::InvalidArgument(
+            "AES-GCM stream already finalized",
+        ))?;
+        self.pending.extend_from_slice(tag);
+        let opened = stream
+            .decrypt_last(Payload {
+                msg: &self.pending,
+                aad: &self.aad,
+            })
+            .map_err(|_| CryptoError::TagMismatch)?;
+        self.pending.clear();
+        Ok(Zeroizing::new(opened))
+    }
+}
+
+/// Hashes a password with Argon2id (default parameters: 19 MiB, 2 passes, 1
+/// lane) and a random 16-byte salt, returning the PHC string to store.
+pub fn argon2_hash(password: &[u8]) -> Result<String, CryptoError> {
+    let salt = SaltString::generate(&mut LibRng);
+    Argon2::default()
+        .hash_password(password, &salt)
+        .map(|hash| hash.to_string())
+        .map_err(|e| CryptoError::PasswordHash(e.to_string()))
+}
+
+/// Checks a password against a stored Argon2 PHC string in constant time,
+/// using the parameters recorded in the string. A string that does not parse
+/// or names another algorithm is `MalformedToken`, distinct from
+/// `PasswordMismatch`.
+pub fn argon2_verify(password: &[u8], phc: &str) -> Result<(), CryptoError> {
+    let hash = PasswordHash::new(phc)
+        .map_err(|_| CryptoError::MalformedToken("invalid PHC hash string"))?;
+    if hash.salt.is_none() || hash.hash.is_none() {
+        return Err(CryptoError::MalformedToken(
+            "PHC hash string has no salt or hash",
+        ));
+    }
+    match Argon2::default().verify_password(password, &hash) {
+        Ok(()) => Ok(()),
+        Err(argon2::password_hash::Error::Password) => Err(CryptoError::PasswordMismatch),
+        Err(_) => Err(CryptoError::MalformedToken(
+            "unusable Argon2 parameters in PHC string",
+        )),
+    }
+}
+
+/// Builds a compact JWS (`header.claims.signature`) signed with RS256. Header
+/// and claims are taken as already-serialized JSON and encoded as base64url
+/// without padding.
+pub fn jwt_sign_rs256(
+    header_json: &[u8],
+    claims_json: &[u8],
+    private_key: &RsaPrivateKey,
+) -> Result<String, CryptoError> {
+    let signing_input = format!(
+        "{}.{}",
+        URL_SAFE_NO_PAD.encode(header_json),
+        URL_SAFE_NO_PAD.encode(claims_json)
+    );
+    let sig = sign_pkcs1v15(private_key, HashAlg::Sha256, signing_input.as_bytes())?;
+    Ok(format!("{signing_input}.{}", URL_SAFE_NO_PAD.encode(sig)))
+}
+
+/// Checks the RS256 signature of a compact JWS. Only the signature is verified:
+/// the header's `alg` and any claims such as `exp` are left to the caller.
+pub fn jwt_verify_rs256(token: &str, public_key: &RsaPublicKey) -> Result<(), CryptoError> {
+    let mut parts = token.split('.');
+    let (header, claims, sig) = match (parts.next(), parts.next(), parts.next(), parts.next()) {
+        (Some(h), Some(c), Some(s), None) => (h, c, s),
+        _ => {
+            return Err(CryptoError::MalformedToken(
+                "expected exactly three segments",
+            ))
+        }
+    };
+
+    if header.is_empty() || claims.is_empty() {
+        return Err(CryptoError::MalformedToken(
+            "empty header or claims segment",
+        ));
+    }
+    if sig.is_empty() {
+        return Err(CryptoError::MalformedToken("missing signature segment"));
+    }
+
+    let sig = URL_SAFE_NO_PAD
+        .decode(sig)
+        .map_err(|_| CryptoError::MalformedToken("signature is not base64url"))?;
+
+    let signing_input = &token[..header.len() + 1 + claims.len()];
+    verify_pkcs1v15(public_key, HashAlg::Sha256, signing_input.as_bytes(), &sig)
+}
+
+/// Re-serializes a JSON document in RFC 8785 (JCS) canonical form: object
+/// members sorted by UTF-16 code units, no insignificant whitespace and
+/// ECMAScript number formatting.
+pub fn canonicalize_json(json: &[u8]) -> Result<Vec<u8>, CryptoError> {
+    let value: serde_json::Value =
+        serde_json::from_slice(json).map_err(CryptoError::InvalidJson)?;
+    serde_jcs::to_vec(&value).map_err(CryptoError::InvalidJson)
+}
+
+/// Signs the JCS canonical form of `json`, so producers that order keys or
+/// space their output differently yield the same signature.
+pub fn sign_json_canonical(
+    private_key: &RsaPrivateKey,
+    hash_alg: HashAlg,
+    json: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    sign_pkcs1v15(private_key, hash_alg, &canonicalize_json(json)?)
+}
+
+/// Verifies a signature made by `sign_json_canonical`.
+pub fn verify_json_canonical(
+    public_key: &RsaPublicKey,
+    hash_alg: HashAlg,
+    json: &[u8],
+    sig: &[u8],
+) -> Result<(), CryptoError> {
+    verify_pkcs1v15(public_key, hash_alg, &canonicalize_json(json)?, sig)
+}
+
+/// Verifies an RSA PKCS#1 v1.5 COSE_Sign1 message (RFC 9052), tagged or
+/// untagged. The digest comes from the protected header's `alg` (RS256, RS384
+/// or RS512). Pass `detached_payload` when the message carries a nil payload;
+/// external AAD is taken to be empty.
+pub fn verify_cose_sign1(
+    message: &[u8],
+    detached_payload: Option<&[u8]>,
+    public_key: &RsaPublicKey,
+) -> Result<(), CryptoError> {
+    let sign1 = CoseSign1::from_tagged_slice(message)
+        .or_else(|_| CoseSign1::from_slice(message))
+        .map_err(|_| CryptoError::MalformedToken("invalid COSE_Sign1 CBOR"))?;
+
+    let hash_alg = match &sign1.protected.header.alg {
+        Some(Algorithm::Assigned(iana::Algorithm::RS256)) => HashAlg::Sha256,
+        Some(Algorithm::Assigned(iana::Algorithm::RS384)) => HashAlg::Sha384,
+        Some(Algorithm::Assigned(iana::Algorithm::RS512)) => HashAlg::Sha512,
+        Some(other) => return Err(CryptoError::UnsupportedAlgId(format!("COSE alg {other:?}"))),
+        None => {
+            return Err(CryptoError::MalformedToken(
+                "COSE_Sign1 protected header has no alg",
+            ))
+        }
+    };
+
+    let tbs = match (&sign1.payload, detached_payload) {
+        (Some(_), None) => sign1.tbs_data(b""),
+        (None, Some(payload)) => sign1.tbs_detached_data(payload, b""),
+        (Some(_), Some(_)) => {
+            return Err(CryptoError::MalformedToken(
+                "COSE_Sign1 payload is attached but a detached payload was given",
+            ))
+        }
+        (None, None) => {
+            return Err(CryptoError::MalformedToken(
+                "COSE_Sign1 payload is detached but none was given",
+            ))
+        }
+    };
+
+    verify_pkcs1v15(public_key, hash_alg, &tbs, &sign1.signature)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand_chacha::rand_core::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+
+    const PRIV_PEM: &str = include_str!("../testdata/priv.pem");
+    const PUB_PEM: &str = include_str!("../testdata/pub.pem");
+
+    // `openssl dgst -sha256 -sign testdata/priv.pem` over the same payload.
+    const GOLDEN_SIG: &str = concat!(
+        "78d5df3217d68145cf04a09c7e6495c45723b12a19d4e3a031e2da022e6397bb",
+        "f526a6e12929f43fb16a019ca46ebba5d2104a8a44fbb49f05192ce62d081fc8",
+        "a33f8066f6da39c36cb385b93c088d371a59a4c6a563208f1cccccce11cd8a1f",
+        "01ca7d92aa459f0c95c86ef8bdb5b36ead8bf838d26b79bc01a5e5573cb001c5",
+        "feb78405db26da42a0f04f1ef0ea7fcf5f2fca4f726607d670b3275bd9a36f21",
+        "25c085972e40c74a5c707e7f6fb4d86c985e5045b7caaa2532439b4f05928075",
+        "e23c95023cc10506c16beaae97e545c9ac09b20c6109f5b7525976ba6ea655b6",
+        "880ed0e334e7baec7d82da9d5433495aa3a8c52860cdd108a51afe5f18cc603a",
+    );
+
+    #[test]
+    fn seeded_sign_matches_pinned_signature() {
+        // Blinding must not leak into the output: every seed gives the same
+        // PKCS#1 v1.5 signature.
+        for seed in [43, 44] {
+            let mut rng = ChaCha20Rng::seed_from_u64(seed);
+            let sig = sign_with_rng(&mut rng, b"golden payload", PRIV_PEM).unwrap();
+            assert_eq!(hex::encode(&sig), GOLDEN_SIG);
+        }
+        verify(
+            b"golden payload",
+            &hex::decode(GOLDEN_SIG).unwrap(),
+            PUB_PEM,
+        )
+        .unwrap();
+    }
+
+    #[test]
+    fn deprecation_table() {
+        use DeprecationStatus::{Deprecated, Forbidden, Ok};
+        use SignatureScheme::{Ecdsa, Ed25519, RsaPkcs1v15, RsaPss};
+
+        let cases = [
+            (RsaPkcs1v15, HashAlg::Sha256, 1024, Some(Forbidden)),
+            (RsaPss, HashAlg::Sha256, 2047, Some(Forbidden)),
+            (RsaPkcs1v15, HashAlg::Sha256, 2048, Some(Deprecated)),
+            (RsaPkcs1v15, HashAlg::Sha256, 3072, Some(Ok)),
+            (RsaPss, HashAlg::Sha3_256, 4096, Some(Ok)),
+            (RsaPkcs1v15, HashAlg::Sha1, 4096, Some(Deprecated)),
+            (RsaPkcs1v15, HashAlg::Sha1, 1024, Some(Forbidden)),
+            (Ecdsa, HashAlg::Sha256, 256, Some(Ok)),
+            (Ecdsa, HashAlg::Sha384, 384, Some(Ok)),
+            (Ecdsa, HashAlg::Sha512, 521, Some(Ok)),
+            (Ecdsa, HashAlg::Sha1, 256, Some(Ok)),
+            (Ecdsa, HashAlg::Sha256, 512, None),
+            (Ed25519, HashAlg::Sha512, 0, Some(Ok)),
+        ];
+        for (scheme, hash_alg, bits, expected) in cases {
+            assert_eq!(
+                deprecation_status(scheme, hash_alg, bits),
+                expected,
+                "{scheme:?} {hash_alg:?} {bits}"
+            );
+        }
+    }
+
+    #[test]
+    fn open_signature_rejects_malformed_containers() {
+        let sealed = seal_signature(SealedAlg::Ps256, b"kid-1", &[0xab; 16]).unwrap();
+        let opened = open_signature(&sealed).unwrap();
+        assert_eq!(opened.alg, SealedAlg::Ps256);
+        assert_eq!(opened.kid, b"kid-1");
+        assert_eq!(opened.sig, [0xab; 16]);
+
+        for len in 0..sealed.len() {
+            assert!(
+                matches!(
+                    open_signature(&sealed[..len]),
+                    Err(CryptoError::MalformedToken(_))
+                ),
+                "truncated to {len} bytes"
+            );
+        }
+
+        let malformed = |offset: usize, byte: u8| {
+            let mut bad = sealed.clone();
+            bad[offset] = byte;
+            open_signature(&bad).map(|_| ())
+        };
+        assert!(matches!(
+            malformed(0, b'X'),
+            Err(CryptoError::MalformedToken(_))
+        ));
+        assert!(matches!(
+            malformed(4, 2),
+            Err(CryptoError::MalformedToken(_))
+        ));
+        assert!(matches!(
+            malformed(5, 99),
+            Err(CryptoError::UnsupportedAlgId(_))
+        ));
+
+        let mut trailing = sealed.clone();
+        trailing.push(0);
+        assert!(matches!(
+            open_signature(&trailing),
+            Err(CryptoError::MalformedToken(_))
+        ));
+
+        let mut empty_sig = sealed[..sealed.len() - 16].to_vec();
+        let sig_len_at = empty_sig.len() - 2;
+        empty_sig[sig_len_at..].copy_from_slice(&[0, 0]);
+        assert!(matches!(
+            open_signature(&empty_sig),
+            Err(CryptoError::MalformedToken(_))
+        ));
+        assert!(seal_signature(SealedAlg::Rs256, b"", b"").is_err());
+    }
+
+    #[test]
+    fn sha3_digests_and_digest_info_prefixes() {
+        // FIPS 202 "abc" vectors, and the DigestInfo prefixes of RFC 8017
+        // section 9.2 with the SHA-3 OIDs (2.16.840.1.101.3.4.2.8-10).
+        let cases = [
+            (
+                HashAlg::Sha3_256,
+                "3a985da74fe225b2045c172d6bd390bd855f086e3e9d525b46bfe24511431532",
+                "3031300d060960864801650304020805000420",
+            ),
+            (
+                HashAlg::Sha3_384,
+                concat!(
+                    "ec01498288516fc926459f58e2c6ad8df9b473cb0fc08c25",
+                    "96da7cf0e49be4b298d88cea927ac7f539f1edf228376d25",
+                ),
+                "3041300d060960864801650304020905000430",
+            ),
+            (
+                HashAlg::Sha3_512,
+                concat!(
+                    "b751850b1a57168a5693cd924b6b096e08f621827444f70d884f5d0240d2712e",
+                    "10e116e9192af3c91a7ec57647e3934057340b4cf408d5a56592f8274eec53f0",
+                ),
+                "3051300d060960864801650304020a05000440",
+            ),
+        ];
+        for (hash_alg, digest, prefix) in cases {
+            assert_eq!(hex::encode(hash_alg.digest(b"abc")), digest, "{hash_alg:?}");
+            assert_eq!(
+                hex::encode(&hash_alg.pkcs1v15().prefix),
+                prefix,
+                "{hash_alg:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn ed25519_matches_rfc8032_and_round_trips() {
+        use ed25519_dalek::pkcs8::{EncodePrivateKey, EncodePublicKey};
+
+        // RFC 8032 section 7.1, TEST 2.
+        let secret =
+            hex::decode("4ccd089b28ff96da9db6c346ec114e0f5b8a319f35aba624da8cf6ed4fb8a6fb");
+        let signing_key = SigningKey::from_bytes(&secret.unwrap().try_into().unwrap());
+        assert_eq!(
+            hex::encode(signing_key.verifying_key().as_bytes()),
+            "3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c"
+        );
+        let sig = ed25519_sign(&signing_key, &[0x72]);
+        assert_eq!(
+            hex::encode(&sig),
+            concat!(
+                "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da",
+                "085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00",
+            )
+        );
+
+        let line_ending = pkcs8::LineEnding::LF;
+        let priv_pem = signing_key.to_pkcs8_pem(line_ending).unwrap();
+        let pub_pem = signing_key
+            .verifying_key()
+            .to_public_key_pem(line_ending)
+            .unwrap();
+        let signing_key = parse_ed25519_private_key_pem(&priv_pem).unwrap();
+        let verifying_key = parse_ed25519_public_key_pem(&pub_pem).unwrap();
+        let sig = ed25519_sign(&signing_key, b"round trip");
+        ed25519_verify(&verifying_key, b"round trip", &sig).unwrap();
+        assert!(ed25519_verify(&verifying_key, b"round trap", &sig).is_err());
+    }
+
+    #[test]
+    fn jwk_thumbprint_matches_rfc7638() {
+        // RFC 7638 section 3.1.
+        let n = concat!(
+            "0vx7agoebGcQSuuPiLJXZptN9nndrQmbXEps2aiAFbWhM78LhWx4cbbfAAtVT86zwu1RK7aPFFxuhDR1L6tSoc_BJECP",
+            "ebWKRXjBZCiFV4n3oknjhMstn64tZ_2W-5JsGY4Hc5n9yBXArwl93lqt7_RN5w6Cf0h4QyQ5v-65YGjQR0_FDW2QvzqY",
+            "368QQMicAtaSqzs8KJZgnYb9c7d0zgdAZHzu6qMQvRL5hajrn1n91CbOpbISD08qNLyrdkt-bFTWhAI4vMQFh6WeZu0f",
+            "M4lFd2NcRwr3XPksINHaQ-G_xBniIqbw0Ls1jF44-csFCur-kEgU8awapJzKnqDKgw",
+        );
+        let n = BigUint::from_bytes_be(&URL_SAFE_NO_PAD.decode(n).unwrap());
+        let public_key = rsa_public_key(n, BigUint::from(65537u32)).unwrap();
+        assert_eq!(
+            jwk_thumbprint(&public_key),
+            "NzbLsXh8uDCcd-6MNwXF4W_7noWXFZAfHkxZsRGC9Xs"
+        );
+    }
+
+    #[test]
+    fn blinded_signature_verifies_with_a_standard_verifier() {
+        let sig = sign(b"blinded", PRIV_PEM).unwrap();
+        let public_key = parse_public_key_pem(PUB_PEM).unwrap();
+        let digest = Sha256::digest(b"blinded");
+        public_key
+            .verify(Pkcs1v15Sign::new::<Sha256>(), &digest, &sig)
+            .unwrap();
+    }
+
+    #[test]
+    fn rsabssa_signature_verifies_as_plain_pss() {
+        let mut rng = ChaCha20Rng::seed_from_u64(121);
+        let private_key = parse_private_key_pem(PRIV_PEM).unwrap();
+        let public_key = parse_public_key_pem(PUB_PEM).unwrap();
+        let msg = b"token nonce";
+
+        let (blinded, state) = rsabssa_blind(&mut rng, &public_key, msg).unwrap();
+        let blind_sig = rsabssa_blind_sign(&mut rng, &private_key, &blinded).unwrap();
+        let sig = rsabssa_finalize(&public_key, &blind_sig, &state).unwrap();
+        assert_ne!(sig, blind_sig);
+
+        // RSABSSA-SHA384-PSS-Deterministic: PSS, SHA-384, MGF1-SHA-384, salt 48.
+        let digest = Sha384::digest(msg);
+        public_key
+            .verify(Pss::new_with_salt::<Sha384>(48), &digest, &sig)
+            .unwrap();
+
+        let (_, other_state) = rsabssa_blind(&mut rng, &public_key, b"other").unwrap();
+        assert!(rsabssa_finalize(&public_key, &blind_sig, &other_state).is_err());
+    }
+}
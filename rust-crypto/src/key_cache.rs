@@ -0,0 +1,68 @@
+//! Opt-in cache of parsed PEM public keys, keyed by the SHA-256 of the PEM
+//! bytes. Disabled (capacity 0) until `set_key_cache_capacity` is called, so
+//! callers that never enable it only pay for one uncontended lock per parse.
+
+use lru::LruCache;
+use rsa::RsaPublicKey;
+use sha2::{Digest, Sha256};
+use std::num::NonZeroUsize;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Mutex, MutexGuard};
+
+use crate::api::CryptoError;
+
+static CACHE: Mutex<Option<LruCache<[u8; 32], RsaPublicKey>>> = Mutex::new(None);
+static HITS: AtomicU64 = AtomicU64::new(0);
+static MISSES: AtomicU64 = AtomicU64::new(0);
+
+// A panic while holding the lock cannot leave the cache half-updated in a way
+// that matters, so recover from poisoning instead of disabling it for good.
+fn cache() -> MutexGuard<'static, Option<LruCache<[u8; 32], RsaPublicKey>>> {
+    CACHE.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+pub(crate) fn set_capacity(capacity: usize) {
+    let mut cache = cache();
+    match (NonZeroUsize::new(capacity), cache.as_mut()) {
+        (None, _) => *cache = None,
+        (Some(cap), Some(c)) => c.resize(cap),
+        (Some(cap), None) => *cache = Some(LruCache::new(cap)),
+    }
+}
+
+pub(crate) fn clear() {
+    if let Some(c) = cache().as_mut() {
+        c.clear();
+    }
+}
+
+pub(crate) fn stats() -> (u64, u64) {
+    (HITS.load(Ordering::Relaxed), MISSES.load(Ordering::Relaxed))
+}
+
+/// Returns the cached key for `pem_bytes`, or parses it with `parse` and caches
+/// the result. Parse failures are not cached.
+pub(crate) fn get_or_parse(
+    pem_bytes: &[u8],
+    parse: impl FnOnce(&[u8]) -> Result<RsaPublicKey, CryptoError>,
+) -> Result<RsaPublicKey, CryptoError> {
+    if cache().is_none() {
+        return parse(pem_bytes);
+    }
+
+    let digest: [u8; 32] = Sha256::digest(pem_bytes).into();
+
+    if let Some(key) = cache().as_mut().and_then(|c| c.get(&digest).cloned()) {
+        HITS.fetch_add(1, Ordering::Relaxed);
+        return Ok(key);
+    }
+
+    // Parse outside the lock; two threads missing on the same key at once just
+    // both parse it.
+    MISSES.fetch_add(1, Ordering::Relaxed);
+    let key = parse(pem_bytes)?;
+    if let Some(c) = cache().as_mut() {
+        c.put(digest, key.clone());
+    }
+    Ok(key)
+}
@@ -0,0 +1,119 @@
+//! Verification of OpenPGP (RFC 4880/9580) detached signatures, as written by
+//! `gpg --detach-sign`, against an OpenPGP transferable public key.
+//!
+//! Only available with the `pgp` feature; without it `verify_detached` always
+//! fails with `NotSupported` so the FFI surface is the same in every build.
+//! Both the signature and the key may be binary or ASCII-armored.
+//!
+//! Only RSA signatures over binary or canonical-text documents are accepted,
+//! with packet versions 3 and 4 and SHA-1, SHA-2 or SHA3-256/512 digests. The
+//! key's self-signatures and subkey bindings are checked, and the signature
+//! is verified against whichever of the primary key and its subkeys matches
+//! the issuer. Key expiry, revocation and usage flags are not checked.
+
+use crate::api::CryptoError;
+
+#[cfg(not(feature = "pgp"))]
+pub(crate) fn verify_detached(
+    _payload: &[u8],
+    _signature: &[u8],
+    _public_key: &[u8],
+) -> Result<(), CryptoError> {
+    Err(CryptoError::NotSupported(
+        "OpenPGP verification (built without the pgp feature)",
+    ))
+}
+
+#[cfg(feature = "pgp")]
+pub(crate) use openpgp::verify_detached;
+
+#[cfg(feature = "pgp")]
+mod openpgp {
+    use super::CryptoError;
+    use pgp::crypto::hash::HashAlgorithm;
+    use pgp::crypto::public_key::PublicKeyAlgorithm;
+    use pgp::packet::{SignatureType, SignatureVersion};
+    use pgp::types::PublicKeyTrait;
+    use pgp::{Deserializable, SignedPublicKey, StandaloneSignature};
+
+    fn is_rsa(alg: PublicKeyAlgorithm) -> bool {
+        matches!(alg, PublicKeyAlgorithm::RSA | PublicKeyAlgorithm::RSASign)
+    }
+
+    fn check_signature(sig: &pgp::Signature) -> Result<(), CryptoError> {
+        if !matches!(
+            sig.config.version(),
+            SignatureVersion::V3 | SignatureVersion::V4
+        ) {
+            return Err(CryptoError::NotSupported(
+                "OpenPGP signature packet version (only 3 and 4 are supported)",
+            ));
+        }
+        if !matches!(sig.typ(), SignatureType::Binary | SignatureType::Text) {
+            return Err(CryptoError::NotSupported(
+                "OpenPGP signature type (only binary and text document signatures are supported)",
+            ));
+        }
+        if !is_rsa(sig.config.pub_alg) {
+            return Err(CryptoError::UnsupportedAlgId(format!(
+                "OpenPGP public-key algorithm {}",
+                u8::from(sig.config.pub_alg)
+            )));
+        }
+        match sig.hash_alg() {
+            HashAlgorithm::SHA1
+            | HashAlgorithm::SHA2_256
+            | HashAlgorithm::SHA2_384
+            | HashAlgorithm::SHA2_512
+            | HashAlgorithm::SHA3_256
+            | HashAlgorithm::SHA3_512 => Ok(()),
+            other => Err(CryptoError::UnsupportedAlgId(format!(
+                "OpenPGP hash algorithm {}",
+                u8::from(other)
+            ))),
+        }
+    }
+
+    // `None` if `key` is not an RSA key named by the signature's issuer.
+    fn try_key(sig: &pgp::Signature, key: &impl PublicKeyTrait, payload: &[u8]) -> Option<bool> {
+        let issuers = sig.issuer();
+        if !is_rsa(key.algorithm()) || !(issuers.is_empty() || issuers.contains(&&key.key_id())) {
+            return None;
+        }
+        Some(sig.verify(key, payload).is_ok())
+    }
+
+    /// Verifies the detached signature `signature` over `payload` with the
+    /// transferable public key `public_key`.
+    pub(crate) fn verify_detached(
+        payload: &[u8],
+        signature: &[u8],
+        public_key: &[u8],
+    ) -> Result<(), CryptoError> {
+        let (sig, _) = StandaloneSignature::from_reader_single(signature)
+            .map_err(|_| CryptoError::MalformedToken("OpenPGP signature packet"))?;
+        let sig = sig.signature;
+        check_signature(&sig)?;
+
+        let (key, _) = SignedPublicKey::from_reader_single(public_key)
+            .map_err(|e| CryptoError::KeyParse(e.to_string()))?;
+        key.verify()
+            .map_err(|e| CryptoError::KeyParse(format!("OpenPGP key self-signature: {e}")))?;
+
+        let primary = try_key(&sig, &key.primary_key, payload);
+        let subkeys = key
+            .public_subkeys
+            .iter()
+            .map(|s| try_key(&sig, &s.key, payload));
+        let outcomes: Vec<bool> = std::iter::once(primary).chain(subkeys).flatten().collect();
+        if outcomes.contains(&true) {
+            Ok(())
+        } else if outcomes.is_empty() {
+            Err(CryptoError::KeyParse(
+                "no RSA key in the OpenPGP certificate matches the signature issuer".into(),
+            ))
+        } else {
+            Err(CryptoError::SignatureMismatch)
+        }
+    }
+}
@@ -0,0 +1,252 @@
+//! Signing with private keys held on a PKCS#11 token, addressed by an RFC 7512
+//! URI such as
+//! `pkcs11:token=prod;object=signing-key?module-path=/usr/lib/softhsm/libsofthsm2.so`.
+//!
+//! Only available with the `pkcs11` feature; without it `sign` always fails
+//! with `NotSupported` so the FFI surface is the same in every build. The key
+//! never leaves the token: the payload is hashed and padded on-device with the
+//! `CKM_SHA*_RSA_PKCS` mechanism matching `hash_alg`, except for SHA-3, which
+//! is hashed here and padded and signed on-device with `CKM_RSA_PKCS`.
+//!
+//! Supported URI path attributes are `token`, `manufacturer`, `model` and
+//! `serial` (which slot to use; the first token matching all of them wins),
+//! and `object`, `id` and `type` (which key; `type` may only be `private`).
+//! `module-path` is the one required query attribute. The PIN is passed
+//! separately rather than through `pin-value`.
+
+use crate::api::{CryptoError, HashAlg};
+
+#[cfg(not(feature = "pkcs11"))]
+pub(crate) fn sign(
+    _uri: &str,
+    _pin: &[u8],
+    _hash_alg: HashAlg,
+    _payload: &[u8],
+) -> Result<Vec<u8>, CryptoError> {
+    Err(CryptoError::NotSupported(
+        "PKCS#11 signing (built without the pkcs11 feature)",
+    ))
+}
+
+#[cfg(feature = "pkcs11")]
+pub(crate) use token::sign;
+
+#[cfg(feature = "pkcs11")]
+mod token {
+    use super::{CryptoError, HashAlg};
+    use cryptoki::context::{CInitializeArgs, Pkcs11};
+    use cryptoki::error::{Error, RvError};
+    use cryptoki::mechanism::Mechanism;
+    use cryptoki::object::{Attribute, ObjectClass};
+    use cryptoki::session::UserType;
+    use cryptoki::types::RawAuthPin;
+    use std::collections::HashMap;
+    use std::sync::{Mutex, MutexGuard};
+
+    // One initialized context per module path, shared by every call: loading
+    // and initializing a module is slow, and a module may only be initialized
+    // once per process. Contexts live until the process exits.
+    static MODULES: Mutex<Option<HashMap<String, Pkcs11>>> = Mutex::new(None);
+
+    fn modules() -> MutexGuard<'static, Option<HashMap<String, Pkcs11>>> {
+        MODULES.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // `Pkcs11` is a shared handle, so the clone refers to the cached context.
+    fn module(path: &str) -> Result<Pkcs11, CryptoError> {
+        let mut modules = modules();
+        let modules = modules.get_or_insert_with(HashMap::new);
+        if let Some(pkcs11) = modules.get(path) {
+            return Ok(pkcs11.clone());
+        }
+        let pkcs11 = Pkcs11::new(path).map_err(token_error)?;
+        pkcs11
+            .initialize(CInitializeArgs::OsThreads)
+            .map_err(token_error)?;
+        modules.insert(path.to_owned(), pkcs11.clone());
+        Ok(pkcs11)
+    }
+
+    #[derive(Default)]
+    struct Pkcs11Uri {
+        token: Option<String>,
+        manufacturer: Option<String>,
+        model: Option<String>,
+        serial: Option<String>,
+        object: Option<Vec<u8>>,
+        id: Option<Vec<u8>>,
+        module_path: Option<String>,
+    }
+
+    fn percent_decode(value: &str) -> Result<Vec<u8>, CryptoError> {
+        let bytes = value.as_bytes();
+        let mut out = Vec::with_capacity(bytes.len());
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] == b'%' {
+                let hex = bytes
+                    .get(i + 1..i + 3)
+                    .and_then(|h| std::str::from_utf8(h).ok());
+                let byte = hex.and_then(|h| u8::from_str_radix(h, 16).ok()).ok_or(
+                    CryptoError::InvalidArgument("PKCS#11 URI has a bad percent-escape"),
+                )?;
+                out.push(byte);
+                i += 3;
+            } else {
+                out.push(bytes[i]);
+                i += 1;
+            }
+        }
+        Ok(out)
+    }
+
+    fn percent_decode_str(value: &str) -> Result<String, CryptoError> {
+        String::from_utf8(percent_decode(value)?)
+            .map_err(|_| CryptoError::InvalidArgument("PKCS#11 URI attribute is not UTF-8"))
+    }
+
+    fn parse_uri(uri: &str) -> Result<Pkcs11Uri, CryptoError> {
+        let rest = uri
+            .strip_prefix("pkcs11:")
+            .ok_or(CryptoError::InvalidArgument(
+                "PKCS#11 URI must start with pkcs11:",
+            ))?;
+        let (path, query) = rest.split_once('?').unwrap_or((rest, ""));
+
+        let mut parsed = Pkcs11Uri::default();
+        for attr in path.split(';').filter(|a| !a.is_empty()) {
+            let (name, value) = attr.split_once('=').ok_or(CryptoError::InvalidArgument(
+                "PKCS#11 URI attribute has no value",
+            ))?;
+            match name {
+                "token" => parsed.token = Some(percent_decode_str(value)?),
+                "manufacturer" => parsed.manufacturer = Some(percent_decode_str(value)?),
+                "model" => parsed.model = Some(percent_decode_str(value)?),
+                "serial" => parsed.serial = Some(percent_decode_str(value)?),
+                "object" => parsed.object = Some(percent_decode(value)?),
+                "id" => parsed.id = Some(percent_decode(value)?),
+                "type" if value == "private" => {}
+                "type" => {
+                    return Err(CryptoError::InvalidArgument(
+                        "PKCS#11 URI type must be private for signing",
+                    ))
+                }
+                _ => {
+                    return Err(CryptoError::InvalidArgument(
+                        "PKCS#11 URI has an unsupported path attribute",
+                    ))
+                }
+            }
+        }
+        for attr in query.split('&').filter(|a| !a.is_empty()) {
+            if let Some(value) = attr.strip_prefix("module-path=") {
+                parsed.module_path = Some(percent_decode_str(value)?);
+            }
+        }
+
+        if parsed.module_path.is_none() {
+            return Err(CryptoError::InvalidArgument(
+                "PKCS#11 URI has no module-path",
+            ));
+        }
+        if parsed.object.is_none() && parsed.id.is_none() {
+            return Err(CryptoError::InvalidArgument(
+                "PKCS#11 URI names no key (object or id)",
+            ));
+        }
+        Ok(parsed)
+    }
+
+    fn token_error(e: Error) -> CryptoError {
+        match e {
+            Error::Pkcs11(RvError::PinIncorrect, _) => CryptoError::BadPassphrase,
+            e => CryptoError::Token(e.to_string()),
+        }
+    }
+
+    // PKCS#11 has no combined SHA-3 RSA mechanisms in `cryptoki`, so for those
+    // the DigestInfo is built here and the token only pads and signs it.
+    fn mechanism_and_input(hash_alg: HashAlg, payload: &[u8]) -> (Mechanism<'static>, Vec<u8>) {
+        match hash_alg {
+            HashAlg::Sha256 => (Mechanism::Sha256RsaPkcs, payload.to_vec()),
+            HashAlg::Sha384 => (Mechanism::Sha384RsaPkcs, payload.to_vec()),
+            HashAlg::Sha512 => (Mechanism::Sha512RsaPkcs, payload.to_vec()),
+            HashAlg::Sha1 => (Mechanism::Sha1RsaPkcs, payload.to_vec()),
+            HashAlg::Sha3_256 | HashAlg::Sha3_384 | HashAlg::Sha3_512 => {
+                let mut digest_info = hash_alg.pkcs1v15().prefix.to_vec();
+                digest_info.extend_from_slice(&hash_alg.digest(payload));
+                (Mechanism::RsaPkcs, digest_info)
+            }
+        }
+    }
+
+    /// Finds the token and key named by `uri` in a session on the cached
+    /// module context, logs in with `pin` (skipped when empty) and signs
+    /// `payload` on the token.
+    pub(crate) fn sign(
+        uri: &str,
+        pin: &[u8],
+        hash_alg: HashAlg,
+        payload: &[u8],
+    ) -> Result<Vec<u8>, CryptoError> {
+        let uri = parse_uri(uri)?;
+        let module_path = uri
+            .module_path
+            .as_deref()
+            .expect("parse_uri requires module-path");
+
+        let pkcs11 = module(module_path)?;
+
+        let matches = |want: &Option<String>, have: &str| {
+            want.as_deref().is_none_or(|w| w == have.trim_end())
+        };
+        let mut slot = None;
+        for candidate in pkcs11.get_slots_with_token().map_err(token_error)? {
+            let info = pkcs11.get_token_info(candidate).map_err(token_error)?;
+            if matches(&uri.token, info.label())
+                && matches(&uri.manufacturer, info.manufacturer_id())
+                && matches(&uri.model, info.model())
+                && matches(&uri.serial, info.serial_number())
+            {
+                slot = Some(candidate);
+                break;
+            }
+        }
+        let slot =
+            slot.ok_or_else(|| CryptoError::Token("no token matches the PKCS#11 URI".into()))?;
+
+        let session = pkcs11.open_ro_session(slot).map_err(token_error)?;
+        // Login state is per token, not per session, so a concurrent call may
+        // already have logged in.
+        if !pin.is_empty() {
+            match session.login_with_raw(UserType::User, &RawAuthPin::new(pin.to_vec())) {
+                Ok(()) | Err(Error::Pkcs11(RvError::UserAlreadyLoggedIn, _)) => {}
+                Err(e) => return Err(token_error(e)),
+            }
+        }
+
+        let mut template = vec![Attribute::Class(ObjectClass::PRIVATE_KEY)];
+        if let Some(label) = uri.object {
+            template.push(Attribute::Label(label));
+        }
+        if let Some(id) = uri.id {
+            template.push(Attribute::Id(id));
+        }
+        let key = match session.find_objects(&template).map_err(token_error)?[..] {
+            [key] => key,
+            [] => {
+                return Err(CryptoError::Token(
+                    "no private key matches the PKCS#11 URI".into(),
+                ))
+            }
+            _ => {
+                return Err(CryptoError::Token(
+                    "more than one private key matches the PKCS#11 URI".into(),
+                ))
+            }
+        };
+
+        let (mechanism, input) = mechanism_and_input(hash_alg, payload);
+        session.sign(&mechanism, key, &input).map_err(token_error)
+    }
+}
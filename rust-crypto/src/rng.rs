@@ -0,0 +1,88 @@
+//! The generator behind every internal random draw: key generation, blinding,
+//! salts, padding and `generate_random_bytes`.
+//!
+//! Which generator that is can be switched at runtime with `set_source`. The
+//! default is the OS RNG. With the `deterministic-rng` feature a third source
+//! exists and is the default: a process-wide ChaCha20 stream seeded by
+//! `set_test_rng_seed` (seed 0 until set), so fuzz crashes and golden outputs
+//! reproduce. That feature makes every key and nonce predictable and is for
+//! tests and fuzzing only; it must never be enabled in a shipped build.
+
+use crate::{RNG_SOURCE_DETERMINISTIC, RNG_SOURCE_OS, RNG_SOURCE_THREAD};
+use rand::{CryptoRng, RngCore};
+use std::sync::atomic::{AtomicI32, Ordering};
+
+#[derive(Clone, Copy, Debug, Default)]
+pub(crate) struct LibRng;
+
+#[cfg(feature = "deterministic-rng")]
+mod seeded {
+    use rand::SeedableRng;
+    use rand_chacha::ChaCha20Rng;
+    use std::sync::{Mutex, MutexGuard};
+
+    static TEST_RNG: Mutex<Option<ChaCha20Rng>> = Mutex::new(None);
+
+    pub(super) fn rng() -> MutexGuard<'static, Option<ChaCha20Rng>> {
+        TEST_RNG.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub(crate) fn set_seed(seed: u64) {
+        *rng() = Some(ChaCha20Rng::seed_from_u64(seed));
+    }
+
+    pub(super) fn with<T>(f: impl FnOnce(&mut ChaCha20Rng) -> T) -> T {
+        f(rng().get_or_insert_with(|| ChaCha20Rng::seed_from_u64(0)))
+    }
+}
+
+#[cfg(feature = "deterministic-rng")]
+pub(crate) use seeded::set_seed;
+
+static SOURCE: AtomicI32 = AtomicI32::new(if cfg!(feature = "deterministic-rng") {
+    RNG_SOURCE_DETERMINISTIC
+} else {
+    RNG_SOURCE_OS
+});
+
+/// Selects the generator for all later draws; false (and no change) for an
+/// unknown source or the deterministic one in a build without the feature.
+pub(crate) fn set_source(source: i32) -> bool {
+    match source {
+        RNG_SOURCE_THREAD | RNG_SOURCE_OS => {}
+        #[cfg(feature = "deterministic-rng")]
+        RNG_SOURCE_DETERMINISTIC => {}
+        _ => return false,
+    }
+    SOURCE.store(source, Ordering::Relaxed);
+    true
+}
+
+fn with_source<T>(f: impl FnOnce(&mut dyn RngCore) -> T) -> T {
+    match SOURCE.load(Ordering::Relaxed) {
+        RNG_SOURCE_THREAD => f(&mut rand::thread_rng()),
+        #[cfg(feature = "deterministic-rng")]
+        RNG_SOURCE_DETERMINISTIC => seeded::with(|r| f(r)),
+        _ => f(&mut rand::rngs::OsRng),
+    }
+}
+
+impl RngCore for LibRng {
+    fn next_u32(&mut self) -> u32 {
+        with_source(|r| r.next_u32())
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        with_source(|r| r.next_u64())
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        with_source(|r| r.fill_bytes(dest))
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), rand::Error> {
+        with_source(|r| r.try_fill_bytes(dest))
+    }
+}
+
+impl CryptoRng for LibRng {}